@@ -0,0 +1,98 @@
+//! Bundling of every profile's output into a single archive for distribution.
+
+use std::{
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+/// Archive to bundle the outputs of all profiles into after rendering.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Archive {
+    /// Leave outputs as they are.
+    #[default]
+    None,
+    /// A `.zip` archive.
+    Zip,
+    /// A gzip-compressed `.tar.gz` archive.
+    TarGz,
+}
+
+impl Archive {
+    fn extension(self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Zip => Some("zip"),
+            Self::TarGz => Some("tar.gz"),
+        }
+    }
+
+    /// Bundles the output directories of `profiles` within `destination` into an archive in
+    /// `destination`, returning its path.
+    pub fn write<'a>(
+        self,
+        destination: &Path,
+        profiles: impl IntoIterator<Item = &'a str>,
+    ) -> anyhow::Result<Option<PathBuf>> {
+        let Some(extension) = self.extension() else {
+            return Ok(None);
+        };
+        let mut profiles = profiles
+            .into_iter()
+            .filter(|profile| destination.join(profile).is_dir())
+            .collect::<Vec<_>>();
+        profiles.sort_unstable();
+
+        let path = destination.join(format!("book.{extension}"));
+        let file = File::create(&path)
+            .with_context(|| format!("Unable to create archive '{}'", path.display()))?;
+        match self {
+            Self::None => unreachable!("no archive is written"),
+            Self::Zip => write_zip(file, destination, &profiles),
+            Self::TarGz => write_tar_gz(file, destination, &profiles),
+        }
+        .with_context(|| format!("Unable to write archive '{}'", path.display()))?;
+        Ok(Some(path))
+    }
+}
+
+fn write_zip(file: File, destination: &Path, profiles: &[&str]) -> anyhow::Result<()> {
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default();
+    for profile in profiles {
+        let entries = walkdir::WalkDir::new(destination.join(profile)).sort_by_file_name();
+        for entry in entries {
+            let entry = entry?;
+            let path = entry.path();
+            // Zip archives always separate path components with forward slashes
+            let name = path
+                .strip_prefix(destination)?
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            if entry.file_type().is_dir() {
+                zip.add_directory(name, options)?;
+            } else if entry.file_type().is_file() {
+                zip.start_file(name, options)?;
+                io::copy(&mut File::open(path)?, &mut zip)?;
+            }
+        }
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+fn write_tar_gz(file: File, destination: &Path, profiles: &[&str]) -> anyhow::Result<()> {
+    let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+    let mut tar = tar::Builder::new(encoder);
+    for profile in profiles {
+        tar.append_dir_all(profile, destination.join(profile))?;
+    }
+    tar.into_inner()?.finish()?;
+    Ok(())
+}
@@ -0,0 +1,92 @@
+//! A persistent cache of assets produced by expensive steps, like rendering diagrams, so they
+//! are only produced once across builds.
+
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use sha2::{Digest, Sha256};
+
+pub struct Cache {
+    dir: PathBuf,
+    dir_relative_to_root: PathBuf,
+    /// Names of the files cached or reused during the current build.
+    used: RefCell<HashSet<String>>,
+}
+
+impl Cache {
+    /// Uses `dir`, which is created once something is cached, as the cache directory of the book
+    /// at `root`.
+    pub fn new(dir: PathBuf, root: &Path) -> Self {
+        Self {
+            dir_relative_to_root: dir.strip_prefix(root).unwrap_or(&dir).to_path_buf(),
+            dir,
+            used: Default::default(),
+        }
+    }
+
+    /// Returns the path, relative to the book's root, of the file with the given extension cached
+    /// under `key` (e.g. the source of a diagram or the URL of an image) in `namespace`. If no such
+    /// file is cached, it is first created by calling `create` with the path to write it to.
+    ///
+    /// Files are named after a SHA-256 hash of their key, which unlike [`std::hash::Hash`] is stable
+    /// across Rust releases and platforms, so the cache stays valid across builds.
+    pub fn get_or_create(
+        &self,
+        namespace: &str,
+        key: impl AsRef<[u8]>,
+        extension: &str,
+        create: impl FnOnce(&Path) -> anyhow::Result<()>,
+    ) -> anyhow::Result<PathBuf> {
+        let hash = Sha256::digest(key.as_ref());
+        let filename = format!("{namespace}-{hash:x}.{extension}");
+        let path = self.dir.join(&filename);
+        if !path.exists() {
+            fs::create_dir_all(&self.dir).with_context(|| {
+                format!("Unable to create cache directory '{}'", self.dir.display())
+            })?;
+            // Create the file under a temporary name so failed or interrupted steps don't leave
+            // partial files behind to be mistaken for cached ones
+            let partial = self.dir.join(format!("{filename}.partial"));
+            if let Err(err) = create(&partial) {
+                let _ = fs::remove_file(&partial);
+                return Err(err);
+            }
+            fs::rename(&partial, &path)
+                .with_context(|| format!("Unable to cache '{}'", path.display()))?;
+        }
+        self.used.borrow_mut().insert(filename.clone());
+        Ok(self.dir_relative_to_root.join(filename))
+    }
+
+    /// Removes files that weren't used during the current build, e.g. diagrams that have since
+    /// been edited or removed from the book, so the cache doesn't grow without bound.
+    pub fn evict_unused(&self) -> anyhow::Result<()> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => {
+                return Err(err).with_context(|| {
+                    format!("Unable to read cache directory '{}'", self.dir.display())
+                })
+            }
+        };
+        let used = self.used.borrow();
+        for entry in entries {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() || used.contains(&*entry.file_name().to_string_lossy())
+            {
+                continue;
+            }
+            log::debug!("Evicting unused cache entry '{}'", entry.path().display());
+            fs::remove_file(entry.path()).with_context(|| {
+                format!("Unable to evict cache entry '{}'", entry.path().display())
+            })?;
+        }
+        Ok(())
+    }
+}
@@ -1,7 +1,7 @@
 use std::{
     collections::{BTreeMap, HashMap},
     fs,
-    path::Path,
+    path::{Path, PathBuf},
 };
 
 use cssparser::CowRcStr;
@@ -35,6 +35,21 @@ pub fn read_stylesheets<'a>(
     })
 }
 
+/// Filters `stylesheets` down to those that exist, warning about any that don't.
+pub fn existing_stylesheets<'a>(
+    stylesheets: &'a [PathBuf],
+    book: &'a crate::Book,
+) -> impl Iterator<Item = &'a Path> {
+    stylesheets.iter().filter_map(|stylesheet| {
+        if book.root.join(stylesheet).is_file() {
+            Some(stylesheet.as_path())
+        } else {
+            log::warn!("CSS stylesheet '{}' does not exist", stylesheet.display());
+            None
+        }
+    })
+}
+
 impl<'i> Css<'i> {
     pub fn load(&mut self, stylesheet: &'i Path, css: &'i str) {
         self.stylesheets.push(stylesheet);
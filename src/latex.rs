@@ -1,4 +1,12 @@
-use std::collections::BTreeSet;
+use std::{
+    collections::{BTreeSet, HashSet},
+    process::Command,
+};
+
+use anyhow::Context as _;
+
+/// Deepest list nesting to set up in LaTeX output; lists nested more deeply are flattened.
+pub const MAX_LIST_DEPTH: usize = 20;
 
 #[derive(Debug, Default)]
 pub struct Packages {
@@ -9,6 +17,20 @@ pub struct Packages {
 pub enum Package {
     FontAwesome,
     EnumItem,
+    FancyHdr,
+    Subcaption,
+    SeqSplit,
+    Svg,
+    Listings,
+    XColor,
+    MakeIdx,
+    MenuKeys,
+    Caption,
+    NewFloat,
+    Minted,
+    Glossaries,
+    Xurl,
+    Sidenotes,
 }
 
 impl Packages {
@@ -16,6 +38,10 @@ impl Packages {
         self.needed.insert(package);
     }
 
+    pub fn is_needed(&self, package: Package) -> bool {
+        self.needed.contains(&package)
+    }
+
     pub fn needed(&self) -> impl Iterator<Item = Package> + '_ {
         self.needed.iter().cloned()
     }
@@ -26,6 +52,138 @@ impl Package {
         match self {
             Self::FontAwesome => "fontawesome",
             Self::EnumItem => "enumitem",
+            Self::FancyHdr => "fancyhdr",
+            Self::Subcaption => "subcaption",
+            Self::SeqSplit => "seqsplit",
+            Self::Svg => "svg",
+            Self::Listings => "listings",
+            Self::XColor => "xcolor",
+            Self::MakeIdx => "makeidx",
+            Self::MenuKeys => "menukeys",
+            Self::Caption => "caption",
+            Self::NewFloat => "newfloat",
+            Self::Minted => "minted",
+            Self::Glossaries => "glossaries",
+            Self::Xurl => "xurl",
+            Self::Sidenotes => "sidenotes",
+        }
+    }
+}
+
+/// Escapes characters with special meaning in LaTeX so `text` is typeset literally.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str(r"\textbackslash{}"),
+            '~' => escaped.push_str(r"\textasciitilde{}"),
+            '^' => escaped.push_str(r"\textasciicircum{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
         }
     }
+    escaped
+}
+
+/// Escapes `text` for use as an `\index` entry, additionally quoting the characters `makeindex`
+/// gives special meaning (e.g. `!` for subentries) so the entry is indexed literally.
+pub fn escape_index_entry(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in escape(text).chars() {
+        if matches!(c, '!' | '@' | '|' | '"') {
+            escaped.push('"');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Lists the names of the lexers Pygments (which `minted` highlights code with) knows, lowercased.
+pub fn pygments_lexers() -> anyhow::Result<HashSet<String>> {
+    let output = Command::new("pygmentize")
+        .args(["-L", "lexers"])
+        .output()
+        .context("Unable to run `pygmentize -L lexers`")?;
+    anyhow::ensure!(
+        output.status.success(),
+        "`pygmentize -L lexers` exited with error code {}",
+        output.status
+    );
+    let output =
+        String::from_utf8(output.stdout).context("`pygmentize -L lexers` output is not UTF8")?;
+    Ok(parse_pygments_lexers(&output))
+}
+
+/// Parses the aliases of each lexer from lines like `* rust, rs:` in `pygmentize -L lexers` output.
+fn parse_pygments_lexers(output: &str) -> HashSet<String> {
+    (output.lines())
+        .filter_map(|line| line.strip_prefix("* "))
+        .flat_map(|aliases| aliases.trim_end().trim_end_matches(':').split(','))
+        .map(|alias| alias.trim().to_lowercase())
+        .collect()
+}
+
+/// Warnings from LaTeX's output that Pandoc doesn't pass on.
+#[derive(Debug, Default)]
+pub struct LogSummary {
+    pub overfull_boxes: Vec<String>,
+    pub undefined_references: Vec<String>,
+}
+
+impl LogSummary {
+    /// Summarizes the last LaTeX run in `output`, since references undefined in earlier runs are
+    /// usually resolved by later ones.
+    pub fn new(output: &str) -> Self {
+        let lines = output.lines().collect::<Vec<_>>();
+        // Each run starts with the engine's banner, e.g. `This is LuaHBTeX, Version 1.17.0`
+        let last_run = (lines.iter())
+            .rposition(|line| line.starts_with("This is ") && line.contains("TeX, Version"))
+            .unwrap_or(0);
+        let mut summary = Self::default();
+        for &line in &lines[last_run..] {
+            let warnings =
+                if line.starts_with(r"Overfull \hbox") || line.starts_with(r"Overfull \vbox") {
+                    &mut summary.overfull_boxes
+                } else if line.starts_with("LaTeX Warning: Reference `")
+                    || line.starts_with("LaTeX Warning: Citation `")
+                {
+                    &mut summary.undefined_references
+                } else {
+                    continue;
+                };
+            if !warnings.iter().any(|warning| warning == line) {
+                warnings.push(line.to_owned());
+            }
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    #[test]
+    fn pygments_lexer_aliases() {
+        let output = indoc! {"
+            Pygments version 2.18.0, (c) 2006-2024 by Georg Brandl, Matthäus Chajdas and contributors.
+
+            Lexers:
+            ~~~~~~~
+            * rust, rs:
+                Rust (filenames *.rs, *.rs.in)
+            * console, shell-session:
+                Bash Session (filenames *.sh-session, *.shell-session)
+        "};
+        let mut lexers = parse_pygments_lexers(output)
+            .into_iter()
+            .collect::<Vec<_>>();
+        lexers.sort();
+        assert_eq!(lexers, ["console", "rs", "rust", "shell-session"]);
+    }
 }
@@ -1,6 +1,8 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fs::{self, File},
+    mem,
+    path::{Path, PathBuf},
 };
 
 use anyhow::{anyhow, Context as _};
@@ -8,13 +10,18 @@ use mdbook::config::HtmlConfig;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 
+mod archive;
+use archive::Archive;
+
 mod book;
 use book::Book;
 
+mod cache;
 mod css;
 mod html;
 mod latex;
 mod pandoc;
+mod url;
 
 mod preprocess;
 use preprocess::Preprocessor;
@@ -27,12 +34,114 @@ struct Config {
     #[serde(default = "defaults::enabled")]
     pub keep_preprocessed: bool,
     pub hosted_html: Option<String>,
+    /// Directory (relative to the book root) in which to cache assets produced by expensive steps
+    /// across builds, defaulting to `.cache` in the build directory.
+    pub cache_dir: Option<PathBuf>,
     /// Code block related configuration.
     #[serde(default = "Default::default")]
     pub code: CodeConfig,
     /// Skip running the renderer.
     #[serde(default = "Default::default")]
     pub disabled: bool,
+    /// Shrink headings in nested chapters by one level per level of nesting.
+    #[serde(default = "defaults::enabled")]
+    pub shrink_nested_headings: bool,
+    /// Mark headings that mdBook would not number or list in its table of contents with Pandoc's
+    /// `unnumbered` and `unlisted` classes.
+    #[serde(default = "defaults::enabled")]
+    pub emit_heading_classes: bool,
+    /// How to number and list the first heading of numbered and unnumbered chapters.
+    #[serde(default = "Default::default")]
+    pub chapter_headings: ChapterHeadings,
+    /// Lay out `<figure>`s containing multiple `<figure>`s as subfigures in LaTeX output.
+    #[serde(default = "Default::default")]
+    pub subfigures: bool,
+    /// Caption images standing alone in paragraphs with their alt text in LaTeX output.
+    #[serde(default = "Default::default")]
+    pub alt_as_caption: bool,
+    /// How to render horizontal rules.
+    #[serde(default = "Default::default")]
+    pub horizontal_rule: HorizontalRule,
+    /// Style the first paragraph following each chapter's first heading as a lead, wrapping it in
+    /// a `Div` with the `lead` class or, in LaTeX output, emphasizing it.
+    #[serde(default = "Default::default")]
+    pub lead_paragraph: bool,
+    /// Drop whitespace between block-level HTML elements in non-HTML output instead of rendering
+    /// it as blocks of its own.
+    #[serde(default = "Default::default")]
+    pub trim_html_whitespace: bool,
+    /// Write the intermediate tree of each chapter to `trees/<chapter>.tree` in each profile's
+    /// build directory to aid debugging.
+    #[serde(default = "Default::default")]
+    pub dump_tree: bool,
+    /// Write the original and resolved destination of every link in the book to `links.json` in
+    /// each profile's build directory to aid debugging.
+    #[serde(default = "Default::default")]
+    pub dump_links: bool,
+    /// Write the command used to run Pandoc to `pandoc-command.sh` and the defaults file passed to
+    /// it to `pandoc-defaults.yaml` in each profile's build directory to aid debugging.
+    #[serde(default = "Default::default")]
+    pub dump_command: bool,
+    /// Names of the parts holding the front and back matter of books using LaTeX's `book` class.
+    #[serde(default = "Default::default")]
+    pub matter_parts: MatterParts,
+    /// How to generate identifiers for headings without explicit ids.
+    #[serde(default = "Default::default")]
+    pub heading_ids: HeadingIds,
+    /// HTML output related configuration.
+    #[serde(default = "Default::default")]
+    pub html: HtmlOutputConfig,
+    /// Admonition related configuration.
+    #[serde(default)]
+    pub admonitions: AdmonitionsConfig,
+    /// Markdown output related configuration.
+    #[serde(default = "Default::default")]
+    pub markdown: MarkdownOutputConfig,
+    /// File extensions of Markdown chapters, considered equivalent when resolving links.
+    #[serde(default = "defaults::markdown_extensions")]
+    pub markdown_extensions: Vec<String>,
+    /// Prefix for identifiers in each chapter in place of Pandoc's file-based prefix, in which
+    /// `{chapter}` is replaced by the chapter's path.
+    pub id_prefix: Option<String>,
+    /// How to handle chapters without any content.
+    #[serde(default = "Default::default")]
+    pub empty_chapters: EmptyChapters,
+    /// How to handle chapters that fail to preprocess.
+    #[serde(default = "Default::default")]
+    pub on_chapter_error: OnChapterError,
+    /// Which files from the source directory to copy for Pandoc to use.
+    #[serde(default = "Default::default")]
+    pub copy_assets: CopyAssets,
+    /// Whether to render lists as tight or loose.
+    #[serde(default = "Default::default")]
+    pub list_spacing: ListSpacing,
+    /// How to render task lists (e.g. `- [x] Complete task`).
+    #[serde(default = "Default::default")]
+    pub task_list_style: TaskListStyle,
+    /// Turn `{{#index term}}` markers into index entries, printing the index at the end of LaTeX
+    /// output.
+    #[serde(default)]
+    pub index: bool,
+    /// Acronyms to define with the `glossaries` package in LaTeX output.
+    #[serde(default)]
+    pub acronyms: AcronymsConfig,
+    /// Line width beyond which Markdown tables are considered wide and given relative column
+    /// widths, defaulting to the profile's `columns`.
+    pub table_columns: Option<usize>,
+    /// Render `<kbd>` elements, including combinations like `<kbd>Ctrl</kbd>+<kbd>C</kbd>`, as
+    /// keys in LaTeX output.
+    #[serde(default)]
+    pub keyboard_shortcuts: bool,
+    /// Render `<span class="sidenote">` elements as margin notes in LaTeX output and as footnotes
+    /// in other formats.
+    #[serde(default)]
+    pub sidenotes: bool,
+    /// `strftime`-style format of the build date used as each profile's `date` metadata, e.g. on
+    /// title pages and in page footers.
+    pub date_format: Option<String>,
+    /// Archive to bundle the outputs of all profiles into after rendering them.
+    #[serde(default = "Default::default")]
+    pub archive: Archive,
 }
 
 /// Configuration for tweaking how code blocks are rendered.
@@ -40,12 +149,324 @@ struct Config {
 #[serde(rename_all = "kebab-case")]
 struct CodeConfig {
     pub show_hidden_lines: bool,
+    /// Remove blank lines at the beginning and end of code blocks.
+    #[serde(default)]
+    pub trim_blank_lines: bool,
+    /// How to render code blocks in languages Pandoc does not know how to highlight.
+    #[serde(default)]
+    pub unknown_language: UnknownLanguage,
+    /// Color added and removed lines of `diff` code blocks in LaTeX output.
+    #[serde(default)]
+    pub style_diffs: bool,
+    /// Languages to highlight code blocks tagged with other names as, e.g. `rs = "rust"`.
+    #[serde(default)]
+    pub language_aliases: HashMap<String, String>,
+    /// Language to highlight console sessions (`console` and `shell-session` code blocks) as,
+    /// e.g. `bash`.
+    pub console_language: Option<String>,
+    /// Render `dot` code blocks as images with Graphviz.
+    #[serde(default)]
+    pub graphviz: bool,
+    /// Merge consecutive code blocks with the same info string into one.
+    #[serde(default)]
+    pub merge_adjacent: bool,
+    /// Length beyond which inline code may break across lines anywhere in LaTeX output.
+    pub break_inline_code_longer_than: Option<usize>,
+}
+
+/// How to render code blocks in languages Pandoc does not know how to highlight.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum UnknownLanguage {
+    /// Pass the language through to Pandoc.
+    #[default]
+    Passthrough,
+    /// Render the code block as plain text.
+    Text,
+    /// Omit the code block from the output.
+    Drop,
+}
+
+/// Configuration for tweaking HTML output.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct HtmlOutputConfig {
+    /// Stylesheets (relative to the book root) to include after those from `[output.html]`.
+    #[serde(default)]
+    pub extra_css: Vec<PathBuf>,
+    /// How to render inline `<svg>` elements in LaTeX output.
+    #[serde(default = "Default::default")]
+    pub svg_in_latex: SvgInLatex,
+    /// Extension to give links to other chapters in HTML output, which are left as relative links
+    /// to separate pages instead of being resolved within the document, e.g. `""` for clean URLs.
+    pub link_extension: Option<String>,
+    /// Class of containers holding tabbed content (e.g. from `{{#tabs}}`) to flatten into a
+    /// subsection per tab, titled by the tab's label, in formats that can't show tabs.
+    pub flatten_tabs: Option<String>,
+    /// Heading level from which to wrap sections in collapsed `<details>` elements, summarized by
+    /// their headings, in HTML output.
+    pub fold_level: Option<usize>,
+    /// Interactive elements (e.g. quizzes) to drop from formats other than HTML, given as class
+    /// selectors like `.quiz` or element names like `quiz-widget`.
+    #[serde(default)]
+    pub strip_interactive: Vec<String>,
+    /// Mark the start of each chapter in HTML output with a comment holding its source path.
+    #[serde(default)]
+    pub annotate_chapters: bool,
+    /// Elements (e.g. custom elements like `custom-callout`) to render as blocks rather than
+    /// inline content.
+    #[serde(default)]
+    pub block_elements: Vec<String>,
+    /// Classes marking elements to render as blocks rather than inline content.
+    #[serde(default)]
+    pub block_classes: Vec<String>,
+}
+
+/// How to render inline `<svg>` elements in LaTeX output.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum SvgInLatex {
+    /// Leave inline SVGs to Pandoc, which cannot include them in LaTeX output.
+    #[default]
+    Rasterize,
+    /// Write each inline SVG to a file and include it with the `svg` package's `\includesvg`,
+    /// preserving vector graphics. Requires Inkscape and running LaTeX with `-shell-escape`.
+    Vector,
+}
+
+/// Configuration for rendering admonitions written as GitHub-style alerts (e.g. `> [!NOTE]`).
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct AdmonitionsConfig {
+    /// How to render admonitions, unless overridden by a profile's `admonition-style`.
+    #[serde(default)]
+    pub style: pandoc::AdmonitionStyle,
+}
+
+/// Configuration for tweaking Markdown output.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct MarkdownOutputConfig {
+    /// How to write hard line breaks.
+    #[serde(default = "Default::default")]
+    pub hard_break: HardBreak,
+    /// Problematic characters to strip or normalize in text.
+    #[serde(default)]
+    pub sanitize: Vec<Sanitize>,
+}
+
+/// How to write hard line breaks (e.g. a backslash at the end of a line) in Markdown output.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum HardBreak {
+    /// Leave them to Pandoc's Markdown writer as line breaks.
+    #[default]
+    Linebreak,
+    /// Write a backslash at the end of the line.
+    Backslash,
+    /// Write two spaces at the end of the line.
+    Spaces,
+}
+
+/// Kinds of characters in text that often break Pandoc or LaTeX, or make for invisible differences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum Sanitize {
+    /// Strip zero-width spaces, joiners, and non-joiners, word joiners, and byte-order marks.
+    ZeroWidth,
+    /// Strip control characters other than tabs and newlines.
+    Control,
+    /// Replace non-breaking spaces with regular spaces.
+    NonBreakingSpace,
+}
+
+impl Sanitize {
+    /// What to replace `c` with, if it is a character of this kind.
+    fn replacement(self, c: char) -> Option<&'static str> {
+        match self {
+            Self::ZeroWidth => matches!(
+                c,
+                '\u{200b}' | '\u{200c}' | '\u{200d}' | '\u{2060}' | '\u{feff}'
+            )
+            .then_some(""),
+            Self::Control => (c.is_control() && !matches!(c, '\t' | '\n' | '\r')).then_some(""),
+            Self::NonBreakingSpace => matches!(c, '\u{a0}' | '\u{202f}').then_some(" "),
+        }
+    }
+}
+
+/// How to render horizontal rules (e.g. `---`).
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum HorizontalRule {
+    /// Render a horizontal line.
+    #[default]
+    Rule,
+    /// Start a new page in LaTeX output, falling back to a horizontal line in other formats.
+    PageBreak,
+    /// Omit horizontal rules from the output.
+    Ignore,
+}
+
+/// How to handle chapters that are empty or contain only whitespace.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum EmptyChapters {
+    /// Warn about the chapter but include it in the output.
+    #[default]
+    Warn,
+    /// Warn about the chapter and omit it from the output.
+    Skip,
+}
+
+/// How to handle chapters that fail to preprocess.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum OnChapterError {
+    /// Log the error and fail the build.
+    #[default]
+    Abort,
+    /// Log the error and omit the chapter from the output.
+    Skip,
+}
+
+/// Which files from the book's source directory to make available to Pandoc.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum CopyAssets {
+    /// Copy the entire source directory before preprocessing.
+    #[default]
+    All,
+    /// Copy only files referenced by links, images, or raw HTML as they are encountered.
+    Referenced,
+}
+
+/// Whether to render lists as tight (without space between items) or loose.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum ListSpacing {
+    /// Render each list as tight or loose as it is in the source.
+    #[default]
+    Auto,
+    /// Render all lists as tight, with items' paragraphs as `Plain` blocks.
+    Tight,
+    /// Render all lists as loose, with items' text in `Para` blocks.
+    Loose,
+}
+
+/// How to render task lists.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum TaskListStyle {
+    /// Prefix each item's text with a checkbox glyph.
+    #[default]
+    Glyphs,
+    /// In LaTeX output, use checkboxes as the items' labels, aligning them like bullets, and
+    /// otherwise fall back to glyphs.
+    Checklist,
+}
+
+/// Algorithm used to generate identifiers for headings.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum HeadingIds {
+    /// Generate the same identifiers as GitHub and mdBook.
+    #[default]
+    Gfm,
+    /// Generate identifiers following Pandoc's `auto_identifiers` extension.
+    Pandoc,
+}
+
+/// Configuration for acronyms defined with the `glossaries` package in LaTeX output.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct AcronymsConfig {
+    /// TOML file (relative to the book root) of `short = "long"` entries.
+    pub file: Option<PathBuf>,
+    /// Where to print the list of acronyms.
+    #[serde(default)]
+    pub list: AcronymList,
+}
+
+/// Where to print the list of acronyms in LaTeX output.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum AcronymList {
+    /// Before the first chapter.
+    Start,
+    /// After the last chapter.
+    #[default]
+    End,
+    /// Don't print the list.
+    None,
+}
+
+/// Names of the parts that divide a LaTeX book into front, main, and back matter.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct MatterParts {
+    pub front: String,
+    pub back: String,
+}
+
+impl Default for MatterParts {
+    fn default() -> Self {
+        Self {
+            front: "Front Matter".into(),
+            back: "Back Matter".into(),
+        }
+    }
+}
+
+/// How to number and list the first heading of each chapter, by whether mdBook numbers the chapter.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+struct ChapterHeadings {
+    pub numbered_chapters: ChapterHeading,
+    pub unnumbered_chapters: ChapterHeading,
+    pub text: ChapterHeadingText,
+}
+
+impl Default for ChapterHeadings {
+    fn default() -> Self {
+        Self {
+            numbered_chapters: ChapterHeading::Numbered,
+            unnumbered_chapters: ChapterHeading::Unnumbered,
+            text: ChapterHeadingText::Heading,
+        }
+    }
+}
+
+/// What to title a chapter's first heading, and so its entry in the table of contents, with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum ChapterHeadingText {
+    /// The heading's own text.
+    Heading,
+    /// The chapter's name in `SUMMARY.md`.
+    Summary,
+}
+
+/// How to number and list a chapter's first heading.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum ChapterHeading {
+    /// Number the heading and list it in the table of contents.
+    Numbered,
+    /// Leave the heading unnumbered but list it in the table of contents, e.g. for a preface.
+    Unnumbered,
+    /// Leave the heading unnumbered and out of the table of contents.
+    Unlisted,
 }
 
 mod defaults {
     pub fn enabled() -> bool {
         true
     }
+
+    pub fn markdown_extensions() -> Vec<String> {
+        ["md", "markdown", "mdown"].map(String::from).into()
+    }
 }
 
 /// A [`mdbook`] backend supporting many output formats by relying on [`pandoc`](https://pandoc.org).
@@ -61,6 +482,81 @@ impl Renderer {
 
     const NAME: &'static str = "pandoc";
     const CONFIG_KEY: &'static str = "output.pandoc";
+
+    /// If the configuration references an external `config-file`, loads it and merges the
+    /// configuration on top of it, so options set in `book.toml` take precedence.
+    fn include_config_file(root: &Path, cfg: toml::Value) -> anyhow::Result<toml::Value> {
+        let Some(path) = cfg.get("config-file") else {
+            return Ok(cfg);
+        };
+        let path = root.join(
+            path.as_str()
+                .context("`config-file` must be a path to a TOML or YAML file")?,
+        );
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Unable to read config file '{}'", path.display()))?;
+        let mut base: toml::Value = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("yaml" | "yml") => serde_yaml::from_str(&contents).map_err(anyhow::Error::from),
+            _ => toml::from_str(&contents).map_err(anyhow::Error::from),
+        }
+        .with_context(|| format!("Unable to parse config file '{}'", path.display()))?;
+        Self::merge(&mut base, cfg);
+        Ok(base)
+    }
+
+    /// Resolves profiles' `base` keys by merging each profile on top of the profile it inherits
+    /// from, so options set in a profile take precedence over those it inherits.
+    fn resolve_profile_bases(cfg: &mut toml::Value) -> anyhow::Result<()> {
+        let Some(toml::Value::Table(profiles)) = cfg.get_mut("profile") else {
+            return Ok(());
+        };
+        let originals = profiles.clone();
+        for (name, profile) in profiles.iter_mut() {
+            let mut inherited = vec![name.clone()];
+            while let Some(base) = profile
+                .as_table_mut()
+                .and_then(|table| table.remove("base"))
+            {
+                let base = base.as_str().with_context(|| {
+                    format!("`base` of profile '{name}' must be a profile name")
+                })?;
+                anyhow::ensure!(
+                    !inherited.iter().any(|profile| profile == base),
+                    "Profile '{name}' inherits from itself through '{base}'"
+                );
+                let mut merged = originals
+                    .get(base)
+                    .with_context(|| {
+                        format!("Profile '{name}' inherits from nonexistent profile '{base}'")
+                    })?
+                    .clone();
+                Self::merge(
+                    &mut merged,
+                    mem::replace(profile, toml::Value::Boolean(false)),
+                );
+                *profile = merged;
+                inherited.push(base.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Merges `overrides` into `base`, recursing into tables present in both.
+    fn merge(base: &mut toml::Value, overrides: toml::Value) {
+        match (base, overrides) {
+            (toml::Value::Table(base), toml::Value::Table(overrides)) => {
+                for (key, val) in overrides {
+                    match base.get_mut(&key) {
+                        Some(existing) => Self::merge(existing, val),
+                        None => {
+                            base.insert(key, val);
+                        }
+                    }
+                }
+            }
+            (base, overrides) => *base = overrides,
+        }
+    }
 }
 
 impl mdbook::Renderer for Renderer {
@@ -93,18 +589,73 @@ impl mdbook::Renderer for Renderer {
             );
         }
 
-        let cfg: Config = ctx
+        let cfg: toml::Value = ctx
             .config
             .get_deserialized_opt(Self::CONFIG_KEY)
             .with_context(|| format!("Unable to deserialize {}", Self::CONFIG_KEY))?
             .ok_or(anyhow!("No {} table found", Self::CONFIG_KEY))?;
+        let mut cfg = Self::include_config_file(&ctx.root, cfg)?;
+        Self::resolve_profile_bases(&mut cfg)?;
+        let cfg: Config = cfg
+            .try_into()
+            .with_context(|| format!("Unable to deserialize {}", Self::CONFIG_KEY))?;
 
         if cfg.disabled {
             log::info!("Skipping rendering since `disabled` is set");
             return Ok(());
         }
 
-        pandoc::check_compatibility()?;
+        let pandoc_version = pandoc::check_compatibility()?;
+
+        let alt_as_caption = cfg.alt_as_caption
+            && (pandoc_version >= pandoc::FIGURE_BLOCKS
+                || {
+                    log::warn!(
+                    "Ignoring `alt-as-caption`, which requires Pandoc {} or newer (using {pandoc_version})",
+                    pandoc::FIGURE_BLOCKS,
+                );
+                    false
+                });
+
+        let highlight_languages = match cfg.code.unknown_language {
+            UnknownLanguage::Passthrough => None,
+            UnknownLanguage::Text | UnknownLanguage::Drop => Some(pandoc::highlight_languages()?),
+        };
+
+        // Pygments fails on languages it has no lexer for, so minted code blocks in those fall
+        // back to plain text
+        let uses_minted = (cfg.profiles.values())
+            .any(|profile| profile.code_block_backend == pandoc::CodeBlockBackend::Minted);
+        let pygments_lexers = match uses_minted.then(latex::pygments_lexers) {
+            None => None,
+            Some(Ok(lexers)) => Some(lexers),
+            Some(Err(err)) => {
+                log::warn!("{err:#}; passing the languages of code blocks through to minted");
+                None
+            }
+        };
+
+        // Catch typos in output formats before running Pandoc on any profile
+        if cfg.profiles.values().any(|profile| profile.to.is_some()) {
+            let output_formats = pandoc::output_formats()?;
+            for (name, profile) in &cfg.profiles {
+                if let Some(to) = profile.to.as_deref() {
+                    pandoc::check_output_format(to, &output_formats)
+                        .with_context(|| format!("Invalid `to` in profile '{name}'"))?;
+                }
+            }
+        }
+
+        let acronyms: BTreeMap<String, String> = match &cfg.acronyms.file {
+            None => Default::default(),
+            Some(file) => {
+                let path = ctx.root.join(file);
+                fs::read_to_string(&path)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|acronyms| toml::from_str(&acronyms).map_err(Into::into))
+                    .with_context(|| format!("Unable to read acronyms from '{}'", path.display()))?
+            }
+        };
 
         let html_cfg: Option<HtmlConfig> = ctx
             .config
@@ -113,6 +664,12 @@ impl mdbook::Renderer for Renderer {
 
         let book = Book::new(ctx)?;
 
+        let cache_dir = match &cfg.cache_dir {
+            Some(dir) => book.root.join(dir),
+            None => book.destination.join(".cache"),
+        };
+        let cache = cache::Cache::new(cache_dir, &book.root);
+
         let stylesheets;
         let mut css = css::Css::default();
         if let Some(cfg) = &html_cfg {
@@ -122,18 +679,78 @@ impl mdbook::Renderer for Renderer {
             }
         }
 
-        for (name, profile) in cfg.profiles {
+        let extra_css = css::existing_stylesheets(&cfg.html.extra_css, &book).collect::<Vec<_>>();
+
+        let profile_names = cfg.profiles.keys().cloned().collect::<Vec<_>>();
+        for (name, mut profile) in cfg.profiles {
+            profile.merge_format_variables();
+            let preserve_md_links = profile.preserve_md_links && profile.writes_markdown();
+            if profile.preserve_md_links && !preserve_md_links {
+                log::warn!(
+                    "Ignoring `preserve-md-links` for profile '{name}' since it does not output Markdown"
+                );
+            }
             let ctx = pandoc::RenderContext {
                 book: &book,
+                cache: &cache,
                 mdbook_cfg: &ctx.config,
-                destination: book.destination.join(name),
+                destination: book.destination.join(&name),
                 output: profile.output_format(),
-                columns: profile.columns,
+                table_columns: cfg.table_columns.unwrap_or(profile.columns),
+                page_break_before: profile.page_break_levels(),
                 cur_list_depth: 0,
                 max_list_depth: 0,
                 code: &cfg.code,
+                highlight_languages: highlight_languages.as_ref(),
+                pygments_lexers: pygments_lexers.as_ref(),
+                shrink_nested_headings: cfg.shrink_nested_headings,
+                emit_heading_classes: cfg.emit_heading_classes,
+                chapter_headings: cfg.chapter_headings,
+                preserve_md_links,
+                subfigures: cfg.subfigures,
+                alt_as_caption,
+                horizontal_rule: cfg.horizontal_rule,
+                markdown_format: profile.markdown_format().map(String::from),
+                hard_break: cfg.markdown.hard_break,
+                sanitize: &cfg.markdown.sanitize,
+                lead_paragraph: cfg.lead_paragraph,
+                trim_html_whitespace: cfg.trim_html_whitespace,
+                dump_tree: cfg.dump_tree,
+                dump_command: cfg.dump_command,
+                dump_links: cfg.dump_links,
+                matter_parts: &cfg.matter_parts,
+                latex_book_class: profile.uses_book_class(),
+                body_environment: profile.body_environment.clone(),
+                max_image_width: profile.max_image_width.clone(),
+                code_block_backend: profile.code_block_backend,
+                url_style: profile.url_style,
+                admonition_style: profile.admonition_style.unwrap_or(cfg.admonitions.style),
+                heading_ids: cfg.heading_ids,
+                pandoc_version,
                 html: html_cfg.as_ref(),
                 css: &css,
+                extra_css: &extra_css,
+                svg_in_latex: cfg.html.svg_in_latex,
+                link_extension: cfg.html.link_extension.as_deref(),
+                flatten_tabs: cfg.html.flatten_tabs.as_deref(),
+                strip_interactive: &cfg.html.strip_interactive,
+                annotate_chapters: cfg.html.annotate_chapters,
+                block_elements: &cfg.html.block_elements,
+                block_classes: &cfg.html.block_classes,
+                fold_level: cfg.html.fold_level,
+                markdown_extensions: &cfg.markdown_extensions,
+                id_prefix: cfg.id_prefix.as_deref(),
+                empty_chapters: cfg.empty_chapters,
+                on_chapter_error: cfg.on_chapter_error,
+                copy_assets: cfg.copy_assets,
+                list_spacing: cfg.list_spacing,
+                task_list_style: cfg.task_list_style,
+                index: cfg.index,
+                acronyms: &acronyms,
+                acronym_list: cfg.acronyms.list,
+                keyboard_shortcuts: cfg.keyboard_shortcuts,
+                sidenotes: cfg.sidenotes,
+                date_format: cfg.date_format.as_deref(),
             };
 
             // Preprocess book
@@ -149,8 +766,7 @@ impl mdbook::Renderer for Renderer {
                     let redirects = redirects
                         .iter()
                         .map(|(src, dst)| (src.as_str(), dst.as_str()));
-                    // In tests, sort redirect map to ensure stable log output
-                    #[cfg(test)]
+                    // Sort redirects so they're processed in the same order across builds
                     let redirects = redirects
                         .collect::<std::collections::BTreeMap<_, _>>()
                         .into_iter();
@@ -164,9 +780,11 @@ impl mdbook::Renderer for Renderer {
             let mut renderer = pandoc::Renderer::new();
 
             // Add preprocessed book chapters to renderer
-            renderer.current_dir(&book.root);
+            let working_dir = (profile.working_dir(&book.root))
+                .with_context(|| format!("Invalid `working-dir` in profile '{name}'"))?;
+            renderer.current_dir(&working_dir);
             for input in &mut preprocessed {
-                renderer.input(input?);
+                renderer.input(book.root.join(input?));
             }
 
             if preprocessed.unresolved_links() {
@@ -176,6 +794,14 @@ impl mdbook::Renderer for Renderer {
                 );
             }
 
+            if cfg.keep_preprocessed {
+                preprocessed.write_manifest()?;
+            }
+
+            if cfg.dump_links {
+                preprocessed.write_links()?;
+            }
+
             if let Some(logfile) = &self.logfile {
                 renderer.stderr(logfile.try_clone()?);
             }
@@ -185,9 +811,17 @@ impl mdbook::Renderer for Renderer {
 
             if !cfg.keep_preprocessed {
                 fs::remove_dir_all(preprocessed.output_dir())?;
+                fs::remove_dir_all(preprocessed.generated_dir())?;
             }
         }
 
+        cache.evict_unused()?;
+
+        let names = profile_names.iter().map(String::as_str);
+        if let Some(archive) = cfg.archive.write(&book.destination, names)? {
+            log::info!("Wrote archive to {}", archive.display());
+        }
+
         Ok(())
     }
 }
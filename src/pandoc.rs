@@ -1,15 +1,29 @@
-use std::{fmt, num::ParseIntError, process::Command, str::FromStr};
+use std::{collections::HashSet, fmt, num::ParseIntError, process::Command, str::FromStr};
 
 use anyhow::{anyhow, Context as _};
 
 mod profile;
-pub use profile::Profile;
+pub use profile::{AdmonitionStyle, CodeBlockBackend, Profile, TocPosition, UrlStyle};
 
 mod renderer;
 pub use renderer::{Context as RenderContext, OutputFormat, Renderer};
 
 pub mod native;
 
+/// First version of Pandoc with `Figure` blocks
+pub const FIGURE_BLOCKS: Version = Version {
+    major: 3,
+    minor: 0,
+    patch: 0,
+};
+
+/// First version of Pandoc with `--embed-resources`, which superseded `--self-contained`
+pub const EMBED_RESOURCES: Version = Version {
+    major: 2,
+    minor: 19,
+    patch: 0,
+};
+
 /// Minimum compatible version of Pandoc
 const MINIMUM_VERSION: Version =
     // Defaults files introduced in 2.8
@@ -75,6 +89,90 @@ pub fn check_compatibility() -> anyhow::Result<Version> {
     }
 }
 
+/// Lists the languages Pandoc is able to syntax highlight.
+pub fn highlight_languages() -> anyhow::Result<HashSet<String>> {
+    let output = Command::new("pandoc")
+        .arg("--list-highlight-languages")
+        .output()
+        .context("Unable to run `pandoc --list-highlight-languages`")?;
+    anyhow::ensure!(
+        output.status.success(),
+        "`pandoc --list-highlight-languages` exited with error code {}",
+        output.status
+    );
+    let output = String::from_utf8(output.stdout)
+        .context("`pandoc --list-highlight-languages` output is not UTF8")?;
+    Ok(output
+        .lines()
+        .map(|line| line.trim().to_lowercase())
+        .collect())
+}
+
+/// Lists the formats Pandoc is able to write.
+pub fn output_formats() -> anyhow::Result<HashSet<String>> {
+    let output = Command::new("pandoc")
+        .arg("--list-output-formats")
+        .output()
+        .context("Unable to run `pandoc --list-output-formats`")?;
+    anyhow::ensure!(
+        output.status.success(),
+        "`pandoc --list-output-formats` exited with error code {}",
+        output.status
+    );
+    let output = String::from_utf8(output.stdout)
+        .context("`pandoc --list-output-formats` output is not UTF8")?;
+    Ok(output.lines().map(|line| line.trim().to_owned()).collect())
+}
+
+/// Checks that `to` names one of `formats`, suggesting the most similar format if it doesn't.
+pub fn check_output_format(to: &str, formats: &HashSet<String>) -> anyhow::Result<()> {
+    // Custom writers are Lua scripts, whose paths may contain `-` (e.g. `lua/my-writer.lua+smart`)
+    let custom_writer = to.contains(['/', '\\'])
+        || (to.match_indices(".lua")).any(|(idx, _)| {
+            matches!(
+                to[idx + ".lua".len()..].chars().next(),
+                None | Some('+' | '-')
+            )
+        });
+    if custom_writer {
+        return Ok(());
+    }
+    // Ignore extensions, e.g. `commonmark_x-smart`
+    let format = to.split(['+', '-']).next().unwrap_or_default();
+    // PDFs are produced by other writers
+    if format == "pdf" || formats.contains(format) {
+        return Ok(());
+    }
+    let suggestion = (formats.iter())
+        .map(|candidate| (edit_distance(format, candidate), candidate))
+        .filter(|&(distance, _)| distance <= 2)
+        .min();
+    match suggestion {
+        Some((_, suggestion)) => {
+            anyhow::bail!("unknown output format '{format}'; did you mean '{suggestion}'?")
+        }
+        None => {
+            anyhow::bail!("unknown output format '{format}' (see `pandoc --list-output-formats`)")
+        }
+    }
+}
+
+/// The Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut distances = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = distances[0];
+        distances[0] = i + 1;
+        for (j, &b) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a != b);
+            diagonal = distances[j + 1];
+            distances[j + 1] = substitution.min(distances[j] + 1).min(diagonal + 1);
+        }
+    }
+    distances[b.len()]
+}
+
 #[cfg(test)]
 mod tests {
     use std::{
@@ -122,6 +220,32 @@ mod tests {
         "#);
     }
 
+    #[test]
+    fn custom_writers() {
+        let formats = HashSet::from(["latex".to_owned(), "html".to_owned()]);
+        for to in [
+            "writer.lua",
+            "my-writer.lua",
+            "my-writer.lua+smart",
+            "./lua/my-writer.lua",
+            "latex-smart",
+            "pdf",
+        ] {
+            assert!(check_output_format(to, &formats).is_ok(), "{to}");
+        }
+        for to in ["my-writer", "latexx", "my-writer.luax"] {
+            assert!(check_output_format(to, &formats).is_err(), "{to}");
+        }
+    }
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(edit_distance("latex", "latex"), 0);
+        assert_eq!(edit_distance("latexx", "latex"), 1);
+        assert_eq!(edit_distance("htlm", "html"), 2);
+        assert_eq!(edit_distance("", "epub"), 4);
+    }
+
     #[test]
     fn versions() {
         let a = Version::from_str("2.10").unwrap();
@@ -136,10 +136,17 @@ impl<'p, 'book, W: io::Write> Serializer<'p, 'book, W> {
     }
 
     pub fn write_attributes(&mut self, attrs: impl Attributes) -> anyhow::Result<()> {
+        let id = attrs.id().unwrap_or("");
+        let prefix = if id.is_empty() {
+            None
+        } else {
+            self.preprocessor.id_prefix()
+        };
         write!(
             self.unescaped(),
-            r#"("{}", "#,
-            attrs.id().unwrap_or("").escape_quotes()
+            r#"("{}{}", "#,
+            prefix.as_deref().unwrap_or("").escape_quotes(),
+            id.escape_quotes()
         )?;
 
         let mut attributes = SerializeList::new(self, Text)?;
@@ -243,6 +250,12 @@ impl<'serializer, 'book, 'p, W: io::Write> SerializeNested<'_, 'serializer, 'boo
         )
     }
 
+    /// Whether the next content starts a new block, i.e. there's no `Plain` block being filled
+    /// with inlines.
+    pub fn is_between_blocks(&self) -> bool {
+        matches!(self, Self::Blocks(_))
+    }
+
     pub fn blocks(&mut self) -> anyhow::Result<&mut SerializeBlocks<'serializer, 'book, 'p, W>> {
         replace_with::replace_with_or_abort_and_return(self, |nested| match nested {
             Self::BlocksSerializingInlines {
@@ -883,6 +896,28 @@ impl<'a, 'book, 'p, W: io::Write> SerializeBlock<'a, 'book, 'p, W> {
         Ok(())
     }
 
+    /// Figure, with attributes, caption, and content (list of blocks)
+    pub fn serialize_figure(
+        self,
+        attrs: impl Attributes,
+        caption: impl FnOnce(&mut SerializeInlines<'_, 'book, 'p, W>) -> anyhow::Result<()>,
+        blocks: impl FnOnce(&mut SerializeBlocks<'_, 'book, 'p, W>) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        write!(self.serializer.unescaped(), "Figure ")?;
+        self.serializer.write_attributes(attrs)?;
+
+        // Caption: (Caption (Maybe ShortCaption) [Block])
+        write!(self.serializer.unescaped(), " (Caption Nothing [Plain ")?;
+        let mut serializer = SerializeList::new(self.serializer, Inline)?;
+        caption(&mut serializer)?;
+        serializer.finish()?;
+        write!(self.serializer.unescaped(), "]) ")?;
+
+        let mut serializer = SerializeList::new(self.serializer, Block)?;
+        blocks(&mut serializer)?;
+        serializer.finish()
+    }
+
     /// Generic block container with attributes
     pub fn serialize_div(
         self,
@@ -935,6 +970,7 @@ impl<'book, 'p, W: io::Write> SerializeCell<'_, 'book, 'p, W> {
     pub fn serialize_cell(
         self,
         attrs: impl Attributes,
+        align: Alignment,
         blocks: impl FnOnce(&mut SerializeBlocks<'_, 'book, 'p, W>) -> anyhow::Result<()>,
     ) -> anyhow::Result<()> {
         write!(self.serializer.unescaped(), "Cell ")?;
@@ -942,7 +978,7 @@ impl<'book, 'p, W: io::Write> SerializeCell<'_, 'book, 'p, W> {
         write!(
             self.serializer.unescaped(),
             " {} (RowSpan 0) (ColSpan 0) ",
-            Alignment::Default.to_native()
+            align.to_native()
         )?;
         let mut serializer = SerializeList::new(self.serializer, Block)?;
         blocks(&mut serializer)?;
@@ -1,5 +1,11 @@
-use std::{collections::BTreeMap, path::PathBuf};
+use std::{
+    collections::BTreeMap,
+    num::NonZeroU32,
+    path::{Path, PathBuf},
+};
 
+use anyhow::Context as _;
+use normpath::PathExt;
 use serde::{Deserialize, Serialize};
 
 use super::OutputFormat;
@@ -15,11 +21,55 @@ pub struct Profile {
     pub number_sections: bool,
     pub output_file: PathBuf,
     pub pdf_engine: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dpi: Option<NonZeroU32>,
     #[serde(default = "defaults::enabled")]
     pub standalone: bool,
     pub to: Option<String>,
     #[serde(default = "defaults::enabled")]
     pub table_of_contents: bool,
+    #[serde(default, skip_serializing)]
+    pub toc_position: TocPosition,
+    #[serde(default, skip_serializing)]
+    pub number_within_chapters: bool,
+    #[serde(default, skip_serializing)]
+    pub page_footer: Option<PageFooter>,
+    #[serde(default, skip_serializing)]
+    pub preserve_md_links: bool,
+    #[serde(default, skip_serializing)]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing)]
+    pub subtitle: Option<String>,
+    #[serde(default, skip_serializing)]
+    pub embed_resources: bool,
+    #[serde(default, skip_serializing)]
+    pub author_format: AuthorFormat,
+    #[serde(default, skip_serializing)]
+    pub page_break_before: Vec<usize>,
+    #[serde(default, skip_serializing)]
+    pub resources: Vec<PathBuf>,
+    #[serde(default, skip_serializing)]
+    pub working_dir: Option<PathBuf>,
+    #[serde(default, skip_serializing)]
+    pub body_environment: Option<String>,
+    #[serde(default, skip_serializing)]
+    pub max_image_width: Option<String>,
+    #[serde(default, skip_serializing)]
+    pub paragraph_spacing: Option<String>,
+    #[serde(default, skip_serializing)]
+    pub openright: bool,
+    #[serde(default, skip_serializing)]
+    pub blank_page_style: Option<String>,
+    #[serde(default, skip_serializing)]
+    pub code_block_backend: CodeBlockBackend,
+    #[serde(default, skip_serializing)]
+    pub description_as_abstract: bool,
+    #[serde(default, skip_serializing)]
+    pub url_style: UrlStyle,
+    #[serde(default, skip_serializing)]
+    pub summarize_latex_log: bool,
+    #[serde(default, skip_serializing)]
+    pub admonition_style: Option<AdmonitionStyle>,
     #[serde(default)]
     pub variables: BTreeMap<String, toml::Value>,
     #[serde(default)]
@@ -28,6 +78,105 @@ pub struct Profile {
     pub rest: BTreeMap<String, toml::Value>,
 }
 
+/// Where to place the table of contents in LaTeX output.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TocPosition {
+    /// Wherever the Pandoc template places it.
+    #[default]
+    Template,
+    /// At the beginning of the document body, after any `include-before` content.
+    BeforeBody,
+    /// At the end of the document body, before any `include-after` content.
+    AfterBody,
+}
+
+/// LaTeX package to typeset code blocks with.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CodeBlockBackend {
+    /// Pandoc's highlighting, with long lines wrapped by `fvextra` if it's installed.
+    #[default]
+    Fvextra,
+    /// The `listings` package, with long lines wrapped.
+    Listings,
+    /// The `minted` package, which highlights code with Pygments and requires running LaTeX with
+    /// `-shell-escape`.
+    Minted,
+}
+
+/// How to render admonitions written as GitHub-style alerts (e.g. `> [!NOTE]`).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AdmonitionStyle {
+    /// A `Div` with the admonition's kind as its class and a `Div` holding its title, as Pandoc's
+    /// `alerts` extension produces.
+    #[default]
+    Div,
+    /// A block quote starting with the admonition's title in bold.
+    Blockquote,
+}
+
+/// How to typeset links to web URLs in LaTeX output.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UrlStyle {
+    /// Leave links as they are.
+    #[default]
+    Inline,
+    /// Move the URLs of links with text of their own to footnotes.
+    Footnote,
+    /// Allow URLs to break across lines anywhere using the `xurl` package.
+    Break,
+}
+
+/// How to pass the book's authors to Pandoc.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AuthorFormat {
+    /// A list of authors, formatted by the Pandoc template.
+    #[default]
+    List,
+    /// A single string joining the authors with commas and "and", e.g. "A, B, and C".
+    Oxford,
+}
+
+impl AuthorFormat {
+    /// Formats `authors` as the value of the `author` metadata field.
+    pub fn format(&self, authors: &[String]) -> toml::Value {
+        match (self, authors) {
+            (Self::List, authors) => authors.to_vec().into(),
+            (Self::Oxford, [author]) => author.as_str().into(),
+            (Self::Oxford, [first, second]) => format!("{first} and {second}").into(),
+            (Self::Oxford, [rest @ .., last]) => format!("{}, and {last}", rest.join(", ")).into(),
+            (Self::Oxford, []) => toml::Value::Array(vec![]),
+        }
+    }
+}
+
+/// Footer to display on each page of LaTeX output.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PageFooter {
+    /// Whether to display the book's authors and the date.
+    Enabled(bool),
+    /// Custom footer text, with `{author}` and `{date}` replaced by the book's authors and the date.
+    Custom(String),
+}
+
+impl PageFooter {
+    const DEFAULT: &'static str = r"{author} \hfill {date}";
+
+    /// The footer's text before substituting `{author}` and `{date}`, if enabled.
+    pub fn template(&self) -> Option<&str> {
+        match self {
+            Self::Enabled(true) => Some(Self::DEFAULT),
+            Self::Enabled(false) => None,
+            Self::Custom(template) => Some(template),
+        }
+    }
+}
+
 mod defaults {
     pub fn enabled() -> bool {
         true
@@ -40,6 +189,27 @@ mod defaults {
 }
 
 impl Profile {
+    /// Merges the table of variables for the profile's output format (`variables.latex` for LaTeX
+    /// or `variables.html` for formats that include raw HTML) over its other variables, dropping
+    /// the tables for other formats.
+    pub fn merge_format_variables(&mut self) {
+        let format = match self.output_format() {
+            OutputFormat::Latex { .. } => Some("latex"),
+            OutputFormat::HtmlLike => Some("html"),
+            OutputFormat::Other => None,
+        };
+        let mut overrides = toml::Table::new();
+        for key in ["latex", "html"] {
+            if let Some(toml::Value::Table(variables)) = self.variables.get(key) {
+                if format == Some(key) {
+                    overrides = variables.clone();
+                }
+                self.variables.remove(key);
+            }
+        }
+        self.variables.extend(overrides);
+    }
+
     pub fn output_format(&self) -> OutputFormat {
         if self.uses_latex() {
             OutputFormat::Latex {
@@ -52,6 +222,82 @@ impl Profile {
         }
     }
 
+    /// Determines whether the profile renders a PDF using LaTeX.
+    pub fn writes_latex_pdf(&self) -> bool {
+        self.output_file
+            .extension()
+            .is_some_and(|extension| extension == "pdf")
+            && self.uses_latex()
+    }
+
+    /// Determines whether the profile outputs a variant of Markdown.
+    pub fn writes_markdown(&self) -> bool {
+        self.markdown_format().is_some()
+    }
+
+    /// The variant of Markdown the profile outputs, if any, without extensions (e.g. `gfm`).
+    pub fn markdown_format(&self) -> Option<&str> {
+        match self.to.as_deref() {
+            Some(to) => {
+                // Ignore extensions, e.g. `commonmark_x-smart`
+                let format = to.split(['+', '-']).next().unwrap_or_default();
+                (format == "markdown"
+                    || format.starts_with("markdown_")
+                    || format.starts_with("commonmark")
+                    || format == "gfm")
+                    .then_some(format)
+            }
+            None => matches!(
+                (self.output_file.extension()).and_then(|extension| extension.to_str()),
+                Some("md" | "markdown")
+            )
+            .then_some("markdown"),
+        }
+    }
+
+    /// The directory to run Pandoc in, which defaults to the root of the book at `root`.
+    pub fn working_dir(&self, root: &Path) -> anyhow::Result<PathBuf> {
+        let Some(dir) = &self.working_dir else {
+            return Ok(root.to_path_buf());
+        };
+        let working_dir = (root.join(dir).normalize())
+            .with_context(|| format!("Working directory '{}' does not exist", dir.display()))?;
+        Ok(working_dir.into_path_buf())
+    }
+
+    /// Determines whether the profile renders to LaTeX with the `book` document class.
+    pub fn uses_book_class(&self) -> bool {
+        self.uses_latex()
+            && self
+                .variables
+                .get("documentclass")
+                .and_then(|class| class.as_str())
+                == Some("book")
+    }
+
+    /// Determines whether the profile's LaTeX document class has chapters, which the default
+    /// `report` class and the `book` class do but classes like `article` don't.
+    pub fn has_chapters(&self) -> bool {
+        matches!(
+            self.variables
+                .get("documentclass")
+                .and_then(|class| class.as_str()),
+            None | Some("report" | "book")
+        )
+    }
+
+    /// Heading levels to start new pages before in LaTeX output, excluding those of chapters, which
+    /// already start on new pages.
+    pub fn page_break_levels(&self) -> Vec<usize> {
+        if !self.uses_latex() {
+            return vec![];
+        }
+        let chapters = self.has_chapters();
+        (self.page_break_before.iter().copied())
+            .filter(|&level| !(chapters && level == 1))
+            .collect()
+    }
+
     /// Formats for which raw HTML is passed through instead of being suppressed.
     /// See <https://pandoc.org/MANUAL.html#extension-raw_html>
     #[allow(unused_parens)]
@@ -1,35 +1,96 @@
 use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    ffi::OsStr,
     fmt::Write as _,
-    fs,
-    io::Write as _,
+    fs::{self, File},
+    io::{self, Write as _},
     mem,
-    path::{Path, PathBuf},
-    process::{Command, Stdio},
+    path::{Component, Path, PathBuf},
+    process::{Command, Output, Stdio},
 };
 
 use anyhow::Context as _;
+use chrono::format::{Item, StrftimeItems};
 use mdbook::config::TextDirection;
 use normpath::PathExt;
 use tempfile::NamedTempFile;
 
-use crate::{book::Book, css, latex, pandoc::Profile, CodeConfig};
+use crate::{
+    book::Book,
+    cache::Cache,
+    css, latex,
+    pandoc::{self, CodeBlockBackend, Profile, TocPosition, UrlStyle},
+    AcronymList, ChapterHeadings, CodeConfig, CopyAssets, EmptyChapters, HardBreak, HeadingIds,
+    HorizontalRule, ListSpacing, MatterParts, OnChapterError, Sanitize, SvgInLatex, TaskListStyle,
+};
 
 pub struct Renderer {
     pandoc: Command,
     num_inputs: usize,
+    stderr: Option<File>,
 }
 
 pub struct Context<'book> {
     pub output: OutputFormat,
     pub destination: PathBuf,
     pub book: &'book Book<'book>,
+    pub cache: &'book Cache,
     pub mdbook_cfg: &'book mdbook::Config,
-    pub columns: usize,
+    pub table_columns: usize,
+    pub page_break_before: Vec<usize>,
     pub cur_list_depth: usize,
     pub max_list_depth: usize,
     pub html: Option<&'book mdbook::config::HtmlConfig>,
     pub(crate) code: &'book CodeConfig,
+    pub highlight_languages: Option<&'book HashSet<String>>,
+    pub pygments_lexers: Option<&'book HashSet<String>>,
+    pub shrink_nested_headings: bool,
+    pub emit_heading_classes: bool,
+    pub(crate) chapter_headings: ChapterHeadings,
+    pub preserve_md_links: bool,
+    pub subfigures: bool,
+    pub alt_as_caption: bool,
+    pub(crate) horizontal_rule: HorizontalRule,
+    pub markdown_format: Option<String>,
+    pub(crate) hard_break: HardBreak,
+    pub(crate) sanitize: &'book [Sanitize],
+    pub lead_paragraph: bool,
+    pub trim_html_whitespace: bool,
+    pub dump_tree: bool,
+    pub dump_command: bool,
+    pub dump_links: bool,
+    pub(crate) matter_parts: &'book MatterParts,
+    pub latex_book_class: bool,
+    pub body_environment: Option<String>,
+    pub max_image_width: Option<String>,
+    pub code_block_backend: pandoc::CodeBlockBackend,
+    pub url_style: pandoc::UrlStyle,
+    pub admonition_style: pandoc::AdmonitionStyle,
+    pub(crate) heading_ids: HeadingIds,
+    pub pandoc_version: pandoc::Version,
     pub css: &'book css::Css<'book>,
+    pub extra_css: &'book [&'book Path],
+    pub(crate) svg_in_latex: SvgInLatex,
+    pub link_extension: Option<&'book str>,
+    pub flatten_tabs: Option<&'book str>,
+    pub strip_interactive: &'book [String],
+    pub annotate_chapters: bool,
+    pub block_elements: &'book [String],
+    pub block_classes: &'book [String],
+    pub fold_level: Option<usize>,
+    pub markdown_extensions: &'book [String],
+    pub id_prefix: Option<&'book str>,
+    pub(crate) empty_chapters: EmptyChapters,
+    pub(crate) on_chapter_error: OnChapterError,
+    pub(crate) copy_assets: CopyAssets,
+    pub(crate) list_spacing: ListSpacing,
+    pub(crate) task_list_style: TaskListStyle,
+    pub index: bool,
+    pub acronyms: &'book BTreeMap<String, String>,
+    pub(crate) acronym_list: AcronymList,
+    pub keyboard_shortcuts: bool,
+    pub sidenotes: bool,
+    pub date_format: Option<&'book str>,
 }
 
 #[derive(Debug)]
@@ -44,11 +105,12 @@ impl Renderer {
         Self {
             pandoc: Command::new("pandoc"),
             num_inputs: 0,
+            stderr: None,
         }
     }
 
-    pub fn stderr(&mut self, cfg: impl Into<Stdio>) -> &mut Self {
-        self.pandoc.stderr(cfg);
+    pub fn stderr(&mut self, file: File) -> &mut Self {
+        self.stderr = Some(file);
         self
     }
 
@@ -57,8 +119,14 @@ impl Renderer {
         self
     }
 
+    /// Adds an input file, given as an absolute path, which is passed to Pandoc relative to its
+    /// working directory.
     pub fn input(&mut self, input: impl AsRef<Path>) -> &mut Self {
-        self.pandoc.arg(input.as_ref());
+        let input = match self.pandoc.get_current_dir() {
+            Some(dir) => relative_to(input.as_ref(), dir),
+            None => input.as_ref().to_path_buf(),
+        };
+        self.pandoc.arg(input);
         self.num_inputs += 1;
         self
     }
@@ -66,24 +134,94 @@ impl Renderer {
     pub fn render(self, mut profile: Profile, ctx: &mut Context) -> anyhow::Result<()> {
         let mut pandoc = self.pandoc;
 
-        profile.output_file = {
-            fs::create_dir_all(&ctx.destination).with_context(|| {
-                format!("Unable to create directory: {}", ctx.destination.display())
+        fs::create_dir_all(&ctx.destination).with_context(|| {
+            format!("Unable to create directory: {}", ctx.destination.display())
+        })?;
+        // An output file of `-` makes Pandoc write to stdout, e.g. for piping into other tools
+        let stdout = profile.output_file == Path::new("-");
+        if !stdout {
+            profile.output_file = ctx.destination.join(&profile.output_file);
+        }
+
+        // Make extra files available alongside the output, e.g. for use by templates
+        let mut copied_resources = HashMap::new();
+        for resource in &profile.resources {
+            let src = ctx.book.root.join(resource);
+            anyhow::ensure!(
+                src.is_file(),
+                "Resource '{}' does not exist",
+                resource.display()
+            );
+            let name = resource.file_name().unwrap();
+            // Resources are copied by name alone, so one would overwrite another with the same name
+            if let Some(other) = copied_resources.insert(name, resource) {
+                anyhow::bail!(
+                    "Resources '{}' and '{}' have the same file name",
+                    other.display(),
+                    resource.display()
+                );
+            }
+            let dest = ctx.destination.join(name);
+            fs::copy(&src, &dest).with_context(|| {
+                format!(
+                    "Unable to copy resource '{}' to {}",
+                    resource.display(),
+                    dest.display()
+                )
             })?;
-            ctx.destination.join(&profile.output_file)
-        };
+        }
 
         pandoc.args(["-f", "native"]);
 
         let mut default_metadata = vec![];
-        if let Some(title) = ctx.mdbook_cfg.book.title.as_deref() {
+        // Profile-specific titles take precedence over the book's title
+        if let Some(title) = (profile.title.as_deref()).or(ctx.mdbook_cfg.book.title.as_deref()) {
             default_metadata.push(("title", title.into()));
+        } else if profile.standalone
+            && !profile.metadata.contains_key("title")
+            && matches!(
+                ctx.output,
+                OutputFormat::Latex { .. } | OutputFormat::HtmlLike
+            )
+            && !profile.writes_markdown()
+        {
+            // Fall back to the first chapter's name rather than producing an untitled document
+            let first_chapter = ctx.book.book.iter().find_map(|item| match item {
+                mdbook::BookItem::Chapter(chapter) if !chapter.name.trim().is_empty() => {
+                    Some(chapter.name.as_str())
+                }
+                _ => None,
+            });
+            match first_chapter {
+                Some(name) => {
+                    log::debug!("Book has no title, using the name of its first chapter: {name}");
+                    default_metadata.push(("title", name.into()));
+                }
+                None => log::debug!("Book has no title and no named chapters to fall back to"),
+            }
+        }
+        if let Some(subtitle) = profile.subtitle.as_deref() {
+            default_metadata.push(("subtitle", subtitle.into()));
         }
         if let Some(description) = ctx.mdbook_cfg.book.description.as_deref() {
             default_metadata.push(("description", description.into()));
+            // Templates like LaTeX's render the abstract, e.g. in an `abstract` environment
+            if profile.description_as_abstract {
+                default_metadata.push(("abstract", description.into()));
+            }
         }
         if !ctx.mdbook_cfg.book.authors.is_empty() {
-            default_metadata.push(("author", ctx.mdbook_cfg.book.authors.clone().into()));
+            let authors = profile.author_format.format(&ctx.mdbook_cfg.book.authors);
+            default_metadata.push(("author", authors));
+        }
+        if let Some(format) = ctx.date_format {
+            let items = StrftimeItems::new(format).collect::<Vec<_>>();
+            anyhow::ensure!(
+                !items.contains(&Item::Error),
+                "Invalid date-format '{format}'"
+            );
+            let date = chrono::Local::now().format_with_items(items.into_iter());
+            default_metadata.push(("date", date.to_string().into()));
         }
         for (key, val) in default_metadata {
             if !profile.metadata.contains_key(key) {
@@ -118,23 +256,130 @@ impl Renderer {
         let mut additional_variables = vec![];
         match &mut ctx.output {
             OutputFormat::Latex { packages } => {
+                // Parts of the book are already rendered as `\part`s, so Pandoc making chapters
+                // into parts too would nest every chapter in a part of its own
+                if profile
+                    .rest
+                    .get("top-level-division")
+                    .and_then(|division| division.as_str())
+                    == Some("part")
+                {
+                    log::warn!(
+                        "Ignoring `top-level-division = \"part\"` since parts of the book are \
+                        already rendered as LaTeX parts"
+                    );
+                    profile.rest.remove("top-level-division");
+                }
+
                 // Enable line breaking in code blocks
-                additional_variables.push((
-                    "header-includes",
-                    r"
+                match profile.code_block_backend {
+                    CodeBlockBackend::Fvextra => additional_variables.push((
+                        "header-includes",
+                        r"
 \IfFileExists{fvextra.sty}{% use fvextra if available to break long lines in code blocks
   \usepackage{fvextra}
   \fvset{breaklines}
 }{}
 "
-                    .into(),
-                ));
+                        .into(),
+                    )),
+                    CodeBlockBackend::Listings => {
+                        packages.need(latex::Package::Listings);
+                        profile.rest.insert("listings".into(), true.into());
+                        additional_variables.push((
+                            "header-includes",
+                            r"\lstset{basicstyle=\ttfamily, columns=fullflexible, breaklines=true}"
+                                .into(),
+                        ));
+                    }
+                    CodeBlockBackend::Minted => {
+                        let shell_escape = ["pdf-engine-opt", "pdf-engine-opts"]
+                            .into_iter()
+                            .filter_map(|key| profile.rest.get(key))
+                            .flat_map(|opts| match opts {
+                                toml::Value::Array(opts) => opts.iter().collect(),
+                                opt => vec![opt],
+                            })
+                            .any(|opt| opt.as_str() == Some("-shell-escape"));
+                        if !shell_escape {
+                            log::warn!(
+                                "The minted code block backend requires running LaTeX with \
+                                `-shell-escape`; add it to `pdf-engine-opts`"
+                            );
+                        }
+                    }
+                }
+
+                if profile.url_style == UrlStyle::Break {
+                    packages.need(latex::Package::Xurl);
+                }
+
+                // Number figures and tables within chapters, e.g. "Figure 2.3", or within sections
+                // for document classes without chapters
+                let number_within = (profile.number_within_chapters).then(|| {
+                    if profile.has_chapters() {
+                        "chapter"
+                    } else {
+                        "section"
+                    }
+                });
+                if let Some(counter) = number_within {
+                    additional_variables.push((
+                        "header-includes",
+                        format!(
+                            r"
+\counterwithin{{figure}}{{{counter}}}
+\counterwithin{{table}}{{{counter}}}
+"
+                        ),
+                    ));
+                }
+
+                // Space paragraphs apart vertically
+                if let Some(spacing) = &profile.paragraph_spacing {
+                    additional_variables.push((
+                        "header-includes",
+                        format!(r"\setlength{{\parskip}}{{{spacing}}}"),
+                    ));
+                }
+
+                // Start chapters on odd pages in double-sided documents
+                if profile.openright {
+                    additional_variables.push(("classoption", "twoside".into()));
+                    additional_variables.push(("classoption", "openright".into()));
+                }
+
+                // Give blank pages inserted before chapters a page style, e.g. without headers
+                if let Some(style) = &profile.blank_page_style {
+                    additional_variables.push((
+                        "header-includes",
+                        format!(
+                            r"
+\makeatletter
+\renewcommand{{\cleardoublepage}}{{\clearpage\if@twoside\ifodd\c@page\else
+  \hbox{{}}\thispagestyle{{{style}}}\newpage\if@twocolumn\hbox{{}}\newpage\fi
+\fi\fi}}
+\makeatother
+"
+                        ),
+                    ));
+                }
 
                 // https://www.overleaf.com/learn/latex/Lists#Lists_for_lawyers:_nesting_lists_to_an_arbitrary_depth
                 const LATEX_DEFAULT_LIST_DEPTH_LIMIT: usize = 4;
 
+                if ctx.max_list_depth > latex::MAX_LIST_DEPTH {
+                    log::warn!(
+                        "Lists are nested {} levels deep, but LaTeX output supports at most {}; \
+                        flattening more deeply nested lists",
+                        ctx.max_list_depth,
+                        latex::MAX_LIST_DEPTH,
+                    );
+                }
+                let max_list_depth = ctx.max_list_depth.min(latex::MAX_LIST_DEPTH);
+
                 // If necessary, extend the max list depth
-                if ctx.max_list_depth > LATEX_DEFAULT_LIST_DEPTH_LIMIT {
+                if max_list_depth > LATEX_DEFAULT_LIST_DEPTH_LIMIT {
                     packages.need(latex::Package::EnumItem);
 
                     let mut include_before = format!(
@@ -153,14 +398,14 @@ impl Renderer {
 
 \renewlist{{enumerate}}{{enumerate}}{{{depth}}}
 ",
-                        depth = ctx.max_list_depth,
+                        depth = max_list_depth,
                     );
 
                     let enumerate_labels =
                         [r"\arabic*", r"\alph*", r"\roman*", r"\Alph*", r"\Roman*"]
                             .into_iter()
                             .cycle();
-                    for (idx, label) in enumerate_labels.take(ctx.max_list_depth).enumerate() {
+                    for (idx, label) in enumerate_labels.take(max_list_depth).enumerate() {
                         writeln!(
                             include_before,
                             r"\setlist[enumerate,{}]{{label=({label})}}",
@@ -185,15 +430,91 @@ impl Renderer {
                     ));
                 }
 
+                // Display a page footer, e.g. with the book's authors and the build date
+                let page_footer = (profile.page_footer.as_ref())
+                    .and_then(|footer| footer.template())
+                    .map(|template| {
+                        packages.need(latex::Package::FancyHdr);
+                        let metadata_text = |key: &str| match profile.metadata.get(key) {
+                            Some(toml::Value::String(text)) => Some(latex::escape(text)),
+                            Some(toml::Value::Array(items)) => Some(
+                                (items.iter())
+                                    .filter_map(|item| item.as_str())
+                                    .map(latex::escape)
+                                    .collect::<Vec<_>>()
+                                    .join(", "),
+                            ),
+                            _ => None,
+                        };
+                        let footer = template
+                            .replace("{author}", &metadata_text("author").unwrap_or_default())
+                            .replace(
+                                "{date}",
+                                &metadata_text("date").unwrap_or_else(|| r"\today".into()),
+                            );
+                        format!(
+                            r"
+\pagestyle{{fancy}}
+\fancyhf{{}}
+\fancyfoot[L]{{{footer}}}
+\fancyfoot[R]{{\thepage}}
+\renewcommand{{\headrulewidth}}{{0pt}}
+\fancypagestyle{{plain}}{{}}
+"
+                        )
+                    });
+
+                if !ctx.acronyms.is_empty() {
+                    packages.need(latex::Package::Glossaries);
+                }
+                // Pandoc's template loads `listings` itself when its `listings` option is enabled
+                let template_loads_listings = profile.standalone
+                    && profile.rest.get("listings").and_then(toml::Value::as_bool) == Some(true);
                 let include_packages = packages
                     .needed()
+                    .filter(|&package| {
+                        !(package == latex::Package::Listings && template_loads_listings)
+                    })
                     .map(|package| format!(r"\usepackage{{{}}}", package.name()))
                     .collect::<Vec<_>>()
                     .join("\n");
                 additional_variables.push(("header-includes", include_packages));
+                // Number code blocks labelled as listings, e.g. "Listing 3", within chapters along
+                // with figures and tables if configured
+                if packages.is_needed(latex::Package::NewFloat) {
+                    let within = number_within
+                        .map_or_else(String::new, |counter| format!(", within={counter}"));
+                    additional_variables.push((
+                        "header-includes",
+                        format!(
+                            r"\DeclareFloatingEnvironment[name=Listing, listname={{List of Listings}}{within}]{{listing}}"
+                        ),
+                    ));
+                }
+                if ctx.index {
+                    additional_variables.push(("header-includes", r"\makeindex".into()));
+                }
+                if !ctx.acronyms.is_empty() {
+                    let mut glossaries = String::from(r"\makeglossaries");
+                    for (short, long) in ctx.acronyms {
+                        write!(
+                            glossaries,
+                            "\n\\newacronym{{{short}}}{{{}}}{{{}}}",
+                            latex::escape(short),
+                            latex::escape(long),
+                        )
+                        .unwrap();
+                    }
+                    additional_variables.push(("header-includes", glossaries));
+                }
+                // Configure the footer only once `fancyhdr` has been loaded
+                if let Some(page_footer) = page_footer {
+                    additional_variables.push(("header-includes", page_footer));
+                }
             }
             OutputFormat::HtmlLike => {
-                for stylesheet in &ctx.css.stylesheets {
+                let stylesheets = ctx.css.stylesheets.iter().chain(ctx.extra_css);
+                for stylesheet in stylesheets {
                     additional_variables.push(("css", stylesheet.to_string_lossy().into_owned()));
                 }
             }
@@ -215,17 +536,82 @@ impl Renderer {
             }
         }
 
+        if profile.table_of_contents && profile.toc_position != TocPosition::Template {
+            if let OutputFormat::Latex { .. } = ctx.output {
+                // Place the table of contents explicitly instead of leaving it up to the template
+                profile.table_of_contents = false;
+                let toc = toml::Value::from(r"\tableofcontents");
+                let (key, append) = match profile.toc_position {
+                    TocPosition::Template => unreachable!(),
+                    // After any existing `include-before` content, immediately before the body
+                    TocPosition::BeforeBody => ("include-before", true),
+                    // Before any existing `include-after` content, immediately after the body
+                    TocPosition::AfterBody => ("include-after", false),
+                };
+                let mut entries = match profile.variables.remove(key) {
+                    None => vec![],
+                    Some(toml::Value::Array(arr)) => arr,
+                    Some(existing) => vec![existing],
+                };
+                if append {
+                    entries.push(toc);
+                } else {
+                    entries.insert(0, toc);
+                }
+                profile
+                    .variables
+                    .insert(key.into(), toml::Value::Array(entries));
+            } else {
+                log::warn!("Ignoring `toc-position` since the output format does not use LaTeX");
+            }
+        }
+
+        if profile.embed_resources {
+            let option = if ctx.pandoc_version >= pandoc::EMBED_RESOURCES {
+                "embed-resources"
+            } else {
+                "self-contained"
+            };
+            profile.rest.insert(option.into(), true.into());
+            // Extracted media would only be embedded right back into the output, leaving behind
+            // a directory of files nothing refers to
+            if profile.rest.remove("extract-media").is_some() {
+                log::warn!(
+                    "Ignoring `extract-media` since `embed-resources` embeds media in the output"
+                );
+            }
+        }
+
+        // Identifiers are already namespaced by `id-prefix`, so Pandoc shouldn't scope them by file
+        // (identifiers that collide across chapters are warned about during preprocessing)
+        if ctx.id_prefix.is_some() {
+            profile.file_scope = false;
+        }
+
+        // Pandoc only passes on LaTeX's output when it's verbose
+        let summarize_latex_log = profile.summarize_latex_log && profile.writes_latex_pdf();
+        if profile.summarize_latex_log && !summarize_latex_log {
+            log::warn!(
+                "Ignoring `summarize-latex-log` since the profile doesn't render a PDF using LaTeX"
+            );
+        } else if summarize_latex_log {
+            (profile.rest).insert("verbosity".into(), "INFO".into());
+        }
+
         let defaults_file = {
             let mut file = NamedTempFile::new()?;
             serde_yaml::to_writer(&mut file, &profile)?;
             file
         };
         pandoc.arg("-d").arg(defaults_file.path());
+        if stdout {
+            pandoc.args(["-o", "-"]);
+        }
 
         // --file-scope only works if there are at least two files, so if there is only one file,
         // add an additionaly empty file to convince Pandoc to perform its link adjustment pass
         let _dummy_tempfile_guard: tempfile::TempPath;
-        if self.num_inputs < 2 {
+        if profile.file_scope && self.num_inputs < 2 {
             let mut dummy = tempfile::Builder::new()
                 .prefix("dummy")
                 .rand_bytes(0)
@@ -235,8 +621,18 @@ impl Renderer {
                 .path()
                 .normalize()
                 .context("failed to normalize dummy file path")?;
-            pandoc.arg(path.as_path().strip_prefix(&ctx.book.root).unwrap());
-            _dummy_tempfile_guard = dummy.into_temp_path();
+            let working_dir = pandoc.get_current_dir().unwrap_or(&ctx.book.root);
+            pandoc.arg(relative_to(path.as_path(), working_dir));
+            if ctx.dump_command {
+                // Keep the file around so the dumped command can be rerun
+                dummy.into_temp_path().keep()?;
+            } else {
+                _dummy_tempfile_guard = dummy.into_temp_path();
+            }
+        }
+
+        if ctx.dump_command {
+            Self::dump_command(&pandoc, defaults_file.path(), &ctx.destination)?;
         }
 
         if log::log_enabled!(log::Level::Trace) {
@@ -244,16 +640,128 @@ impl Renderer {
         } else {
             log::info!("Running pandoc");
         }
-        let status = pandoc
-            .stdin(Stdio::null())
-            .status()
-            .context("Unable to run `pandoc`")?;
+        pandoc.stdin(Stdio::null());
+        let status = if summarize_latex_log {
+            let output = (pandoc.stdout(Stdio::inherit()).stderr(Stdio::piped()))
+                .output()
+                .context("Unable to run `pandoc`")?;
+            Self::summarize_latex_log(&output, self.stderr.as_ref())?;
+            output.status
+        } else {
+            if let Some(stderr) = &self.stderr {
+                pandoc.stderr(stderr.try_clone()?);
+            }
+            pandoc.status().context("Unable to run `pandoc`")?
+        };
         anyhow::ensure!(status.success(), "pandoc exited unsuccessfully");
 
-        let outfile = &profile.output_file;
-        let outfile = outfile.strip_prefix(&ctx.book.root).unwrap_or(outfile);
-        log::info!("Wrote output to {}", outfile.display());
+        if stdout {
+            log::info!("Wrote output to stdout");
+        } else {
+            let outfile = &profile.output_file;
+            let outfile = outfile.strip_prefix(&ctx.book.root).unwrap_or(outfile);
+            log::info!("Wrote output to {}", outfile.display());
+        }
 
         Ok(())
     }
+
+    /// Passes on the warnings and errors in Pandoc's verbose `output` to `stderr`, or all of it if
+    /// Pandoc failed, and logs a summary of the warnings in LaTeX's output within it.
+    fn summarize_latex_log(output: &Output, stderr: Option<&File>) -> anyhow::Result<()> {
+        let verbose = String::from_utf8_lossy(&output.stderr);
+        let mut passed_on = String::new();
+        // Messages, like LaTeX's output, can span several lines following their level
+        let mut in_info = false;
+        for line in verbose.lines() {
+            if line.starts_with("[INFO]") {
+                in_info = true;
+            } else if line.starts_with("[WARNING]") || line.starts_with("[ERROR]") {
+                in_info = false;
+            }
+            if !output.status.success() || !in_info {
+                writeln!(passed_on, "{line}")?;
+            }
+        }
+        match stderr {
+            Some(mut file) => file.write_all(passed_on.as_bytes())?,
+            None => io::stderr().write_all(passed_on.as_bytes())?,
+        }
+
+        let summary = latex::LogSummary::new(&verbose);
+        if !summary.overfull_boxes.is_empty() {
+            log::warn!(
+                "LaTeX reported {} overfull boxes:\n{}",
+                summary.overfull_boxes.len(),
+                summary.overfull_boxes.join("\n"),
+            );
+        }
+        if !summary.undefined_references.is_empty() {
+            log::warn!(
+                "LaTeX reported {} undefined references:\n{}",
+                summary.undefined_references.len(),
+                summary.undefined_references.join("\n"),
+            );
+        }
+        Ok(())
+    }
+
+    /// Writes the defaults file to `pandoc-defaults.yaml` and a script running `pandoc` with it to
+    /// `pandoc-command.sh` in `destination` so Pandoc can be rerun outside of mdBook.
+    fn dump_command(pandoc: &Command, defaults: &Path, destination: &Path) -> anyhow::Result<()> {
+        let dumped_defaults = destination.join("pandoc-defaults.yaml");
+        fs::copy(defaults, &dumped_defaults)
+            .with_context(|| format!("Unable to write file: {}", dumped_defaults.display()))?;
+
+        let quote = |arg: &OsStr| {
+            let arg = arg.to_string_lossy();
+            if !arg.is_empty()
+                && (arg.chars()).all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c))
+            {
+                arg.into_owned()
+            } else {
+                format!("'{}'", arg.replace('\'', r"'\''"))
+            }
+        };
+        let mut script = String::new();
+        if let Some(dir) = pandoc.get_current_dir() {
+            writeln!(script, "cd {}", quote(dir.as_os_str()))?;
+        }
+        script.push_str(&quote(pandoc.get_program()));
+        for arg in pandoc.get_args() {
+            let arg = if arg == defaults {
+                dumped_defaults.as_os_str()
+            } else {
+                arg
+            };
+            write!(script, " {}", quote(arg))?;
+        }
+        script.push('\n');
+
+        let path = destination.join("pandoc-command.sh");
+        fs::write(&path, script)
+            .with_context(|| format!("Unable to write file: {}", path.display()))
+    }
+}
+
+/// Expresses the absolute `path` relative to the absolute directory `base`, leaving it absolute if
+/// the two have nothing in common (e.g. are on different drives).
+fn relative_to(path: &Path, base: &Path) -> PathBuf {
+    let mut path_components = path.components().peekable();
+    let mut base_components = base.components().peekable();
+    let mut common = 0;
+    while let (Some(a), Some(b)) = (path_components.peek(), base_components.peek()) {
+        if a != b {
+            break;
+        }
+        path_components.next();
+        base_components.next();
+        common += 1;
+    }
+    if common == 0 {
+        return path.to_path_buf();
+    }
+    (base_components.map(|_| Component::ParentDir))
+        .chain(path_components)
+        .collect()
 }
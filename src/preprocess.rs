@@ -1,22 +1,26 @@
 use std::{
     borrow::{Borrow, Cow},
     cmp,
-    collections::{hash_map::DefaultHasher, HashMap, VecDeque},
+    collections::{
+        hash_map::{self, DefaultHasher},
+        BTreeMap, HashMap, HashSet, VecDeque,
+    },
     ffi::OsString,
     fmt::{self, Display, Write},
     fs::{self, File},
     hash::{Hash, Hasher},
     io::{self, Write as _},
-    iter,
+    iter, mem,
     num::NonZeroU32,
     ops::Range,
     path::{Path, PathBuf},
-    str,
+    process, str,
 };
 
 use anyhow::{anyhow, Context};
 use ego_tree::NodeId;
 use html5ever::{expanded_name, local_name, namespace_url, ns, tendril::format_tendril};
+use indexmap::IndexMap;
 use log::log;
 use mdbook::{
     book::{BookItems, Chapter},
@@ -26,33 +30,62 @@ use normpath::PathExt;
 use once_cell::sync::Lazy;
 use pulldown_cmark::{CowStr, Event, HeadingLevel, LinkType, Tag, TagEnd};
 use regex::Regex;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
 use walkdir::WalkDir;
 
-use crate::pandoc::{self, native::ColWidth, OutputFormat, RenderContext};
+use crate::{
+    latex,
+    pandoc::{self, native::ColWidth, OutputFormat, RenderContext},
+    url, AcronymList, ChapterHeading, ChapterHeadingText, CopyAssets, EmptyChapters, HeadingIds,
+    OnChapterError, Sanitize,
+};
 
 mod code;
 
 pub mod tree;
-use tree::{Element, MdElement, Node, QualNameExt, TreeBuilder};
+use tree::{BlockQuoteKind, Element, MdElement, Node, QualNameExt, TreeBuilder};
 
 pub struct Preprocessor<'book> {
     pub(crate) ctx: RenderContext<'book>,
     preprocessed: PathBuf,
     preprocessed_relative_to_root: PathBuf,
-    redirects: HashMap<PathBuf, String>,
+    /// Directory of files generated for Pandoc (e.g. to print the index), kept out of the
+    /// preprocessed source directory so they can't collide with the book's own files.
+    generated: PathBuf,
+    redirects: IndexMap<PathBuf, String>,
     hosted_html: Option<&'book str>,
     unresolved_links: bool,
     chapters: HashMap<&'book Path, IndexedChapter<'book>>,
+    /// Files outside of the source directory, keyed by the hashed names they are given within the
+    /// preprocessed directory.
+    hashed_paths: BTreeMap<PathBuf, PathBuf>,
+    /// Original and resolved destinations of the links in each chapter, if `dump-links` is set.
+    links: BTreeMap<PathBuf, Vec<Link>>,
+    /// Names of the chapters in which heading ids were first used, tracking explicit ids and, if
+    /// `id-prefix` doesn't namespace identifiers by chapter, generated ones.
+    heading_ids: HashMap<String, String>,
 }
 
 pub struct Preprocess<'book> {
     preprocessor: Preprocessor<'book>,
     items: BookItems<'book>,
     part_num: usize,
+    /// Whether the most recent part was the front matter of a LaTeX book.
+    in_front_matter: bool,
+    /// Whether the beginning and end of the LaTeX body environment have been emitted.
+    began_body: bool,
+    ended_body: bool,
+    /// Whether the index has been printed at the end of the book.
+    printed_index: bool,
+    /// Whether the list of acronyms has been printed at the start or end of the book.
+    printed_acronyms: bool,
 }
 
 struct IndexedChapter<'book> {
     chapter: &'book Chapter,
+    /// Name substituted for `{chapter}` in identifier prefixes, unique among the book's chapters.
+    slug: String,
     anchors: Option<ChapterAnchors<'book>>,
 }
 
@@ -62,6 +95,17 @@ struct ChapterAnchors<'book> {
     beginning: Option<CowStr<'book>>,
 }
 
+/// Identifier of an anchor emitted at the beginning of chapters consisting only of raw HTML, which
+/// have no headings for links to the chapter to point to.
+pub const CHAPTER_START_ANCHOR: &str = "mdbook-pandoc-chapter-start";
+
+/// A link's destination as written and as resolved, dumped to `links.json` if `dump-links` is set.
+#[derive(Serialize)]
+struct Link {
+    original: String,
+    resolved: String,
+}
+
 #[derive(Debug)]
 struct NormalizedPath {
     src_absolute_path: PathBuf,
@@ -93,19 +137,36 @@ impl Display for UnresolvableRemoteImageError {
 
 impl std::error::Error for UnresolvableRemoteImageError {}
 
-pub struct UnresolvableRemoteImage;
+/// An image that can't be included, so its alt text should be rendered in its place.
+pub struct UnresolvableImage;
 
 impl<'book> Preprocessor<'book> {
     pub fn new(ctx: RenderContext<'book>) -> anyhow::Result<Self> {
         let preprocessed = ctx.destination.join("src");
+        let generated = ctx.destination.join("generated");
 
-        if preprocessed.try_exists()? {
-            fs::remove_dir_all(&preprocessed)?;
+        for dir in [&preprocessed, &generated] {
+            if dir.try_exists()? {
+                fs::remove_dir_all(dir)?;
+            }
+            fs::create_dir_all(dir)?;
         }
-        fs::create_dir_all(&preprocessed)?;
 
-        for entry in WalkDir::new(&ctx.book.source_dir).follow_links(true) {
-            let entry = entry?;
+        // Otherwise, files are copied as they are referenced
+        let walk = (ctx.copy_assets == CopyAssets::All)
+            .then(|| WalkDir::new(&ctx.book.source_dir).follow_links(true));
+        for entry in walk.into_iter().flatten() {
+            let entry = match entry {
+                // Following a symlink that points to one of its ancestors would recurse forever
+                Err(err) if err.loop_ancestor().is_some() => {
+                    log::warn!(
+                        "Skipping symlink '{}' since it points to one of its parent directories",
+                        err.path().unwrap_or(Path::new("")).display(),
+                    );
+                    continue;
+                }
+                entry => entry?,
+            };
             let src = entry.path();
             if src.starts_with(ctx.book.destination.as_path()) {
                 continue;
@@ -122,6 +183,7 @@ impl<'book> Preprocessor<'book> {
         }
 
         let mut chapters = HashMap::new();
+        let mut slugs = HashSet::new();
         for section in ctx.book.book.iter() {
             if let BookItem::Chapter(
                 chapter @ Chapter {
@@ -130,8 +192,29 @@ impl<'book> Preprocessor<'book> {
                 },
             ) = section
             {
+                // Surface chapters listed in `SUMMARY.md` whose files have gone missing upfront,
+                // since they would otherwise quietly render as empty chapters
+                let source = ctx.book.source_dir.join(path);
+                if let Err(err) = File::open(&source) {
+                    log::warn!(
+                        "Unable to read file '{}' of chapter '{}': {err}",
+                        source.display(),
+                        chapter.name,
+                    );
+                }
+                // Paths like `a/b.md` and `a_b.md` have the same kebab-cased form, so disambiguate
+                // later chapters with a numeric suffix to keep their identifiers apart
+                let base = Self::make_kebab_case(&path.with_extension("").to_string_lossy());
+                let mut slug = base.clone();
+                for n in 2.. {
+                    if slugs.insert(slug.clone()) {
+                        break;
+                    }
+                    slug = format!("{base}-{n}");
+                }
                 let chapter = IndexedChapter {
                     chapter,
+                    slug,
                     anchors: Default::default(),
                 };
                 chapters.insert(path.as_path(), chapter);
@@ -144,10 +227,14 @@ impl<'book> Preprocessor<'book> {
                 .unwrap_or(&preprocessed)
                 .to_path_buf(),
             preprocessed,
+            generated,
             redirects: Default::default(),
             hosted_html: Default::default(),
             unresolved_links: false,
             chapters,
+            hashed_paths: Default::default(),
+            links: Default::default(),
+            heading_ids: Default::default(),
             ctx,
         })
     }
@@ -205,8 +292,17 @@ impl<'book> Preprocessor<'book> {
                         .context("Unable to normalize redirect source")?
                         .preprocessed_path_relative_to_root;
 
+                    // The destination has already been resolved through any redirects registered
+                    // so far, so one leading back to the source would close a cycle
+                    let dst = dst.into_string();
+                    anyhow::ensure!(
+                        Path::new(&dst) != src,
+                        "Cycle in redirects: '{}' redirects back to itself",
+                        src.display()
+                    );
+
                     log::debug!("Registered redirect: {} => {dst}", src.display());
-                    self.redirects.insert(src, dst.into_string());
+                    self.redirects.insert(src, dst);
                     Ok(())
                 })
                 .map_err(|err| (err, entry))
@@ -226,6 +322,11 @@ impl<'book> Preprocessor<'book> {
             items: self.ctx.book.book.iter(),
             preprocessor: self,
             part_num: 0,
+            in_front_matter: false,
+            began_body: false,
+            ended_body: false,
+            printed_index: false,
+            printed_acronyms: false,
         }
     }
 
@@ -240,7 +341,9 @@ impl<'book> Preprocessor<'book> {
             return link;
         };
         let chapter_dir = chapter_path.parent().unwrap();
-        self.normalize_link(chapter_path, chapter_dir, link_type, link, ctx)
+        let original = self.ctx.dump_links.then(|| link.to_string());
+        let normalized = self
+            .normalize_link(chapter_path, chapter_dir, link_type, link, ctx)
             .unwrap_or_else(|(err, link)| {
                 log::warn!(
                     "Unable to normalize link '{}' in chapter '{}': {err:#}",
@@ -248,7 +351,14 @@ impl<'book> Preprocessor<'book> {
                     chapter.name,
                 );
                 link
-            })
+            });
+        if let Some(original) = original {
+            (self.links.entry(chapter_path.to_path_buf()).or_default()).push(Link {
+                original,
+                resolved: normalized.to_string(),
+            });
+        }
+        normalized
     }
 
     fn normalize_link<'link>(
@@ -314,14 +424,28 @@ impl<'book> Preprocessor<'book> {
                 let path_range = link_path_range();
                 let link_path = match &link[path_range] {
                     // Internal reference within chapter
-                    "" if link.starts_with('#') => return Ok(link),
+                    "" if link.starts_with('#') => {
+                        return Ok(match self.ctx.id_prefix {
+                            Some(template) => {
+                                let prefix = self.make_id_prefix(template, chapter_path);
+                                format!("#{prefix}{}", &link[1..]).into()
+                            }
+                            None => link,
+                        });
+                    }
                     path => Path::new(path),
                 };
-                let path = if let Ok(relative_to_root) = link_path.strip_prefix("/") {
-                    self.preprocessed.join(relative_to_root)
-                } else {
-                    chapter_dir.join(link_path)
+                let resolve = |link_path: &Path| {
+                    if let Ok(relative_to_root) = link_path.strip_prefix("/") {
+                        self.preprocessed.join(relative_to_root)
+                    } else {
+                        chapter_dir.join(link_path)
+                    }
                 };
+                let path = resolve(link_path);
+                // Paths are sometimes percent-encoded, occasionally more than once (e.g. `%2520`)
+                let decoded_path =
+                    url::decode(&link[path_range]).map(|decoded| resolve(Path::new(&decoded)));
 
                 enum LinkDestination<'a> {
                     PartiallyResolved(NormalizedPath),
@@ -334,12 +458,37 @@ impl<'book> Preprocessor<'book> {
                         self.normalize_path(&self.preprocessed.join(&path))
                             .map_err(|_| err)
                     })
+                    .or_else(|err| match link_path.strip_prefix("/") {
+                        // Files that haven't been copied yet are only in the source directory
+                        Ok(relative_to_root) => self
+                            .normalize_path(&self.ctx.book.source_dir.join(relative_to_root))
+                            .map_err(|_| err),
+                        Err(_) => Err(err),
+                    })
+                    .or_else(|err| match &decoded_path {
+                        Some(decoded_path) => self
+                            .normalize_path(&self.ctx.book.source_dir.join(decoded_path))
+                            .map_err(|_| err),
+                        None => Err(err),
+                    })
+                    .or_else(|err| match ctx {
+                        // Images in content included from another directory (e.g. with
+                        // `{{#include}}`) may be relative to the source directory instead
+                        LinkContext::Image if path != link_path && !link_path.has_root() => self
+                            .normalize_path(&self.ctx.book.source_dir.join(link_path))
+                            .map_err(|_| err),
+                        LinkContext::Image | LinkContext::Link => Err(err),
+                    })
                     .and_then(|normalized| {
                         if let Some(mut path) = self
                             .redirects
                             .get(&normalized.preprocessed_path_relative_to_root)
                         {
+                            let mut followed = HashSet::from([path]);
                             while let Some(dest) = self.redirects.get(Path::new(path)) {
+                                if !followed.insert(dest) {
+                                    anyhow::bail!("Cycle in redirects through '{dest}'");
+                                }
                                 path = dest;
                             }
                             Ok(LinkDestination::FullyResolved(Cow::Borrowed(path)))
@@ -353,21 +502,56 @@ impl<'book> Preprocessor<'book> {
                 let normalized_link = match normalized_path {
                     Err(err) => Err((err, link)),
                     Ok(normalized_path) => {
-                        let (normalized_path, add_anchor) = match normalized_path {
-                            LinkDestination::FullyResolved(path) => (path, None),
+                        let (normalized_path, add_anchor, id_prefix) = match normalized_path {
+                            LinkDestination::FullyResolved(path) => (path, None, None),
                             LinkDestination::PartiallyResolved(normalized_path) => {
+                                // Leave links to Markdown files as written so they remain
+                                // human-friendly in Markdown output
+                                if self.ctx.preserve_md_links
+                                    && (normalized_path.src_absolute_path.extension()).is_some_and(
+                                        |extension| {
+                                            (self.ctx.markdown_extensions.iter())
+                                                .any(|md| extension == md.as_str())
+                                        },
+                                    )
+                                {
+                                    return Ok(link);
+                                }
+
+                                let relative_path = normalized_path
+                                    .preprocessed_path_relative_to_root
+                                    .strip_prefix(&self.preprocessed_relative_to_root)
+                                    .unwrap();
+
+                                // Link to chapters as separate pages, e.g. on a static site
+                                if let (Some(extension), OutputFormat::HtmlLike) =
+                                    (self.ctx.link_extension, &self.ctx.output)
+                                {
+                                    if self.chapters.contains_key(relative_path) {
+                                        let page = Path::new(&link[path_range])
+                                            .with_extension(extension.trim_start_matches('.'));
+                                        return match os_to_utf8(page.into_os_string()) {
+                                            Ok(page) => {
+                                                let mut link = link.into_string();
+                                                link.replace_range(path_range, &page);
+                                                Ok(link.into())
+                                            }
+                                            Err(err) => Err((err, link)),
+                                        };
+                                    }
+                                }
+
                                 // Check whether link is anchored (points to a section within a document)
                                 let already_anchored = link[path_range.end..].contains('#');
+                                let id_prefix = (self.ctx.id_prefix)
+                                    .filter(|_| self.chapters.contains_key(relative_path))
+                                    .map(|template| self.make_id_prefix(template, relative_path));
 
                                 // As of version 3.2, pandoc no longer generates an anchor at the beginning
                                 // of each file, so we need to find alternate destination for chapter links
                                 let add_anchor = if already_anchored {
                                     None
                                 } else {
-                                    let relative_path = normalized_path
-                                        .preprocessed_path_relative_to_root
-                                        .strip_prefix(&self.preprocessed_relative_to_root)
-                                        .unwrap();
                                     let chapter = self.chapters.get_mut(relative_path);
                                     match chapter {
                                         None => {
@@ -380,10 +564,14 @@ impl<'book> Preprocessor<'book> {
                                         Some(IndexedChapter {
                                             chapter,
                                             ref mut anchors,
+                                            ..
                                         }) => {
                                             let anchors = match anchors {
                                                 Some(anchors) => anchors,
-                                                None => match ChapterAnchors::new(chapter) {
+                                                None => match ChapterAnchors::new(
+                                                    chapter,
+                                                    self.ctx.heading_ids,
+                                                ) {
                                                     Ok(found) => anchors.insert(found),
                                                     Err(err) => return Err((err, link)),
                                                 },
@@ -406,18 +594,28 @@ impl<'book> Preprocessor<'book> {
                                         .preprocessed_path_relative_to_root
                                         .into_os_string(),
                                 ) {
-                                    Ok(path) => (path.into(), add_anchor),
+                                    Ok(path) => (path.into(), add_anchor, id_prefix),
                                     Err(err) => return Err((err, link)),
                                 }
                             }
                         };
 
                         let mut link = link.into_string();
-                        link.replace_range(path_range, &normalized_path);
+                        if let Some(prefix) = id_prefix {
+                            // Pandoc doesn't scope prefixed identifiers by file, so link to the
+                            // identifier within the chapter directly
+                            let anchor = add_anchor.map(|a| a.as_ref()).or_else(|| {
+                                let (_, anchor) = link[path_range.end..].split_once('#')?;
+                                Some(anchor)
+                            });
+                            link = format!("#{prefix}{}", anchor.unwrap_or_default());
+                        } else {
+                            link.replace_range(path_range, &normalized_path);
 
-                        if let Some(anchor) = add_anchor {
-                            link.push('#');
-                            link.push_str(anchor);
+                            if let Some(anchor) = add_anchor {
+                                link.push('#');
+                                link.push_str(anchor);
+                            }
                         }
 
                         Ok(link.into())
@@ -456,6 +654,17 @@ impl<'book> Preprocessor<'book> {
         }
     }
 
+    /// Generates an identifier for a heading with the provided content using the given algorithm.
+    fn make_identifier<E>(heading_ids: HeadingIds, content: impl IntoIterator<Item = E>) -> String
+    where
+        E: Borrow<Event<'book>>,
+    {
+        match heading_ids {
+            HeadingIds::Gfm => Self::make_gfm_identifier(content),
+            HeadingIds::Pandoc => Self::make_pandoc_identifier(content),
+        }
+    }
+
     /// Generates a GitHub Markdown-flavored identifier for a heading with the provided content.
     fn make_gfm_identifier<E>(content: impl IntoIterator<Item = E>) -> String
     where
@@ -477,6 +686,38 @@ impl<'book> Preprocessor<'book> {
         id
     }
 
+    /// Generates an identifier for a heading with the provided content following Pandoc's
+    /// [`auto_identifiers`](https://pandoc.org/MANUAL.html#extension-auto_identifiers) extension.
+    fn make_pandoc_identifier<E>(content: impl IntoIterator<Item = E>) -> String
+    where
+        E: Borrow<Event<'book>>,
+    {
+        let mut id = String::new();
+        for event in content {
+            match event.borrow() {
+                Event::Text(text) | Event::Code(text) => {
+                    for c in text.chars() {
+                        match c {
+                            c if c.is_whitespace() => id.push('-'),
+                            c @ ('-' | '_' | '.') => id.push(c),
+                            c if c.is_alphanumeric() => id.extend(c.to_lowercase()),
+                            _ => {}
+                        }
+                    }
+                }
+                Event::SoftBreak | Event::HardBreak => id.push('-'),
+                _ => {}
+            }
+        }
+        // Identifiers may not begin with a number or punctuation mark
+        let start = id.find(char::is_alphabetic).unwrap_or(id.len());
+        id.drain(..start);
+        if id.is_empty() {
+            id.push_str("section");
+        }
+        id
+    }
+
     fn download_remote_image(&self, link: &str) -> anyhow::Result<PathBuf> {
         match ureq::get(link).call() {
             Err(err) => Err(UnresolvableRemoteImageError { err }.into()),
@@ -516,20 +757,29 @@ impl<'book> Preprocessor<'book> {
     /// - Is a relative path
     /// - Does not traverse parent directories
     /// - Uniquely corresponds to the file at the original path
-    fn normalize_path(&self, path: &Path) -> anyhow::Result<NormalizedPath> {
+    fn normalize_path(&mut self, path: &Path) -> anyhow::Result<NormalizedPath> {
+        let markdown_extensions = self.ctx.markdown_extensions;
         let absolute_path = path
             .normalize()
-            .or_else(|err| match path.extension() {
-                Some(extension) if extension == "html" => {
-                    if path.file_stem().is_some_and(|name| name == "index") {
-                        if let Ok(path) = path.with_file_name("README.md").normalize() {
-                            return Ok(path);
-                        }
+            .or_else(|err| {
+                let extension = path.extension().unwrap_or_default();
+                let is_markdown = markdown_extensions
+                    .iter()
+                    .any(|md| extension == md.as_str());
+                if extension == "html" && path.file_stem().is_some_and(|name| name == "index") {
+                    if let Ok(path) = path.with_file_name("README.md").normalize() {
+                        return Ok(path);
                     }
-                    path.with_extension("md").normalize()
                 }
-                Some(extension) if extension == "md" => path.with_extension("html").normalize(),
-                _ => Err(err),
+                if extension != "html" && !is_markdown {
+                    return Err(err);
+                }
+                // Links to chapters may use the extension of the HTML file mdBook renders or of
+                // any of the Markdown files it renders from
+                (iter::once("html").chain(markdown_extensions.iter().map(String::as_str)))
+                    .filter(|&candidate| extension != candidate)
+                    .find_map(|candidate| path.with_extension(candidate).normalize().ok())
+                    .ok_or(err)
             })
             .with_context(|| format!("Unable to normalize path: {}", path.display()))?
             .into_path_buf();
@@ -545,6 +795,8 @@ impl<'book> Preprocessor<'book> {
                 if let Some(extension) = absolute_path.extension() {
                     name.set_extension(extension);
                 }
+                self.hashed_paths
+                    .insert(name.clone(), absolute_path.clone());
                 name
             });
 
@@ -557,6 +809,18 @@ impl<'book> Preprocessor<'book> {
         })
     }
 
+    /// Generates the prefix for identifiers in the chapter at `chapter_path` (relative to the
+    /// source directory) by replacing `{chapter}` in `template` with the chapter's path.
+    fn make_id_prefix(&self, template: &str, chapter_path: &Path) -> String {
+        let chapter = match self.chapters.get(chapter_path) {
+            Some(chapter) => Cow::Borrowed(chapter.slug.as_str()),
+            None => {
+                Self::make_kebab_case(&chapter_path.with_extension("").to_string_lossy()).into()
+            }
+        };
+        template.replace("{chapter}", &chapter)
+    }
+
     fn make_kebab_case(s: &str) -> String {
         const SEPARATORS: &[char] = &['_', '/', '.', '&', '?', '='];
         s
@@ -571,8 +835,35 @@ impl Iterator for Preprocess<'_> {
     type Item = anyhow::Result<PathBuf>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if !mem::replace(&mut self.began_body, true) {
+            if let Some(res) = self.body_environment("begin").transpose() {
+                return Some(res);
+            }
+        }
+        if self.preprocessor.ctx.acronym_list == AcronymList::Start
+            && !mem::replace(&mut self.printed_acronyms, true)
+        {
+            if let Some(res) = self.print_acronyms().transpose() {
+                return Some(res);
+            }
+        }
         loop {
-            let item = self.items.next()?;
+            let Some(item) = self.items.next() else {
+                if !mem::replace(&mut self.printed_acronyms, true) {
+                    if let Some(res) = self.print_acronyms().transpose() {
+                        return Some(res);
+                    }
+                }
+                if !mem::replace(&mut self.printed_index, true) {
+                    if let Some(res) = self.print_index().transpose() {
+                        return Some(res);
+                    }
+                }
+                if mem::replace(&mut self.ended_body, true) {
+                    return None;
+                }
+                return self.body_environment("end").transpose();
+            };
             if let Some(res) = self.preprocess_book_item(item).transpose() {
                 break Some(res);
             }
@@ -587,6 +878,15 @@ impl<'book> Preprocess<'book> {
                 let Some(chapter_path) = &chapter.source_path else {
                     return Ok(None);
                 };
+                if chapter_content(chapter).trim().is_empty() {
+                    match self.preprocessor.ctx.empty_chapters {
+                        EmptyChapters::Warn => log::warn!("Chapter '{}' is empty", chapter.name),
+                        EmptyChapters::Skip => {
+                            log::warn!("Skipping empty chapter '{}'", chapter.name);
+                            return Ok(None);
+                        }
+                    }
+                }
                 let chapter_path = self.preprocessor.ctx.book.source_dir.join(chapter_path);
                 let normalized = self.preprocessor.normalize_path(&chapter_path)?;
                 let mut writer = io::BufWriter::new(normalized.create()?);
@@ -609,7 +909,13 @@ impl<'book> Preprocess<'book> {
                             log::error!("Failed to read partially preprocessed chapter: {err}")
                         }
                     }
-                    return Err(err);
+                    return match self.preprocessor.ctx.on_chapter_error {
+                        OnChapterError::Abort => Err(err),
+                        OnChapterError::Skip => {
+                            log::error!("Skipping chapter '{}': {err:#}", chapter.name);
+                            Ok(None)
+                        }
+                    };
                 }
                 Ok(Some(normalized.preprocessed_path_relative_to_root))
             }
@@ -628,10 +934,24 @@ impl<'book> Preprocess<'book> {
                         .create_new(true)
                         .open(self.preprocessor.preprocessed.join(&path))
                         .with_context(|| format!("Unable to create file for part '{name}'"))?;
-                    writeln!(
-                        file,
-                        r#"[Para [RawInline (Format "latex") "\\part{{{name}}}"]]"#
-                    )?;
+                    let part = format!(r#"Para [RawInline (Format "latex") "\\part{{{name}}}"]"#);
+                    let raw_latex = |latex: &str| format!(r#"RawBlock (Format "latex") "{latex}""#);
+                    let ctx = &self.preprocessor.ctx;
+                    // The book class divides books into front, main, and back matter
+                    let blocks = if !ctx.latex_book_class {
+                        vec![part]
+                    } else if *name == ctx.matter_parts.front {
+                        self.in_front_matter = true;
+                        vec![raw_latex(r"\\frontmatter")]
+                    } else if *name == ctx.matter_parts.back {
+                        self.in_front_matter = false;
+                        vec![raw_latex(r"\\backmatter")]
+                    } else if mem::take(&mut self.in_front_matter) {
+                        vec![raw_latex(r"\\mainmatter"), part]
+                    } else {
+                        vec![part]
+                    };
+                    writeln!(file, "[{}]", blocks.join(", "))?;
                     Ok(Some(
                         self.preprocessor.preprocessed_relative_to_root.join(path),
                     ))
@@ -644,6 +964,56 @@ impl<'book> Preprocess<'book> {
         }
     }
 
+    /// Writes a file that begins or ends (depending on `command`) the environment wrapping the
+    /// body of LaTeX output, if one is configured.
+    fn body_environment(&mut self, command: &str) -> anyhow::Result<Option<PathBuf>> {
+        let ctx = &self.preprocessor.ctx;
+        let (OutputFormat::Latex { .. }, Some(environment)) = (&ctx.output, &ctx.body_environment)
+        else {
+            return Ok(None);
+        };
+        let raw = format!(r"\\{command}{{{environment}}}");
+        self.write_raw_latex(&format!("body-{command}.md"), &raw)
+            .with_context(|| format!("Unable to create file to {command} the body environment"))
+            .map(Some)
+    }
+
+    /// Writes a file that prints the index at the end of LaTeX output, if enabled.
+    fn print_index(&mut self) -> anyhow::Result<Option<PathBuf>> {
+        let ctx = &mut self.preprocessor.ctx;
+        let (true, OutputFormat::Latex { packages }) = (ctx.index, &mut ctx.output) else {
+            return Ok(None);
+        };
+        packages.need(latex::Package::MakeIdx);
+        self.write_raw_latex("printindex.md", r"\\printindex")
+            .context("Unable to create file to print the index")
+            .map(Some)
+    }
+
+    /// Writes a file that prints the list of acronyms in LaTeX output, if any are defined.
+    fn print_acronyms(&mut self) -> anyhow::Result<Option<PathBuf>> {
+        let ctx = &self.preprocessor.ctx;
+        if !matches!(ctx.output, OutputFormat::Latex { .. })
+            || ctx.acronyms.is_empty()
+            || ctx.acronym_list == AcronymList::None
+        {
+            return Ok(None);
+        }
+        self.write_raw_latex("printglossaries.md", r"\\printglossaries")
+            .context("Unable to create file to print the list of acronyms")
+            .map(Some)
+    }
+
+    /// Writes a file named `name` to the directory of generated files containing a raw LaTeX
+    /// block, returning the file's path relative to the book's root.
+    fn write_raw_latex(&self, name: &str, latex: &str) -> anyhow::Result<PathBuf> {
+        let path = self.preprocessor.generated.join(name);
+        let mut file = File::options().write(true).create_new(true).open(&path)?;
+        writeln!(file, r#"[RawBlock (Format "latex") "{latex}"]"#)?;
+        let root = &self.preprocessor.ctx.book.root;
+        Ok(path.strip_prefix(root).unwrap_or(&path).to_path_buf())
+    }
+
     fn preprocess_chapter(
         &mut self,
         chapter: &'book Chapter,
@@ -666,14 +1036,64 @@ impl<'book> Preprocess<'book> {
         &self.preprocessor.preprocessed
     }
 
+    pub fn generated_dir(&self) -> &Path {
+        &self.preprocessor.generated
+    }
+
     pub fn unresolved_links(&self) -> bool {
         self.preprocessor.unresolved_links
     }
+
+    /// Writes a manifest mapping the hashed names of files from outside the source directory
+    /// back to their original paths, if any such files were copied into the preprocessed directory.
+    ///
+    /// The manifest is written next to the preprocessed directory rather than within it, where it
+    /// could overwrite a file of the same name from the source directory.
+    pub fn write_manifest(&self) -> anyhow::Result<()> {
+        let preprocessor = &self.preprocessor;
+        if preprocessor.hashed_paths.is_empty() {
+            return Ok(());
+        }
+        let path = preprocessor.ctx.destination.join(Self::MANIFEST);
+        let mut manifest = io::BufWriter::new(
+            File::create(&path)
+                .with_context(|| format!("Unable to create file: {}", path.display()))?,
+        );
+        for (hashed, original) in &preprocessor.hashed_paths {
+            let original = original
+                .strip_prefix(&preprocessor.ctx.book.root)
+                .unwrap_or(original);
+            writeln!(manifest, "{} => {}", hashed.display(), original.display())?;
+        }
+        manifest.flush()?;
+        Ok(())
+    }
+
+    const MANIFEST: &'static str = "src-manifest.txt";
+
+    /// Writes the original and resolved destinations of the links in each chapter as JSON.
+    pub fn write_links(&self) -> anyhow::Result<()> {
+        let preprocessor = &self.preprocessor;
+        let path = preprocessor.ctx.destination.join(Self::LINKS);
+        let mut file = io::BufWriter::new(
+            File::create(&path)
+                .with_context(|| format!("Unable to create file: {}", path.display()))?,
+        );
+        serde_json::to_writer_pretty(&mut file, &preprocessor.links)
+            .with_context(|| format!("Unable to write links to {}", path.display()))?;
+        writeln!(file)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    const LINKS: &'static str = "links.json";
 }
 
 pub struct PreprocessChapter<'book, 'preprocessor> {
     pub(crate) preprocessor: &'preprocessor mut Preprocessor<'book>,
     chapter: &'book Chapter,
+    /// The chapter's content, without any leading byte-order mark.
+    content: &'book str,
     part_num: usize,
     parser: Parser<'book>,
     stack: Vec<NodeId>,
@@ -685,26 +1105,29 @@ pub struct PreprocessChapter<'book, 'preprocessor> {
 struct Parser<'book> {
     lookahead: VecDeque<(Event<'book>, Range<usize>)>,
     parser: pulldown_cmark::OffsetIter<'book, pulldown_cmark::DefaultBrokenLinkCallback>,
+    /// Whether the events consumed so far consist only of raw HTML blocks.
+    html_only: HtmlOnly,
 }
 
 impl<'book> Parser<'book> {
-    fn new(md: &'book str) -> Self {
-        /// Markdown extensions supported by mdBook
-        ///
-        /// See https://rust-lang.github.io/mdBook/format/markdown.html#extensions
-        const PARSER_OPTIONS: pulldown_cmark::Options = {
-            use pulldown_cmark::Options;
-            Options::empty()
-                .union(Options::ENABLE_STRIKETHROUGH)
-                .union(Options::ENABLE_FOOTNOTES)
-                .union(Options::ENABLE_TABLES)
-                .union(Options::ENABLE_TASKLISTS)
-                .union(Options::ENABLE_HEADING_ATTRIBUTES)
-        };
+    /// Markdown extensions supported by mdBook
+    ///
+    /// See https://rust-lang.github.io/mdBook/format/markdown.html#extensions
+    const OPTIONS: pulldown_cmark::Options = {
+        use pulldown_cmark::Options;
+        Options::empty()
+            .union(Options::ENABLE_STRIKETHROUGH)
+            .union(Options::ENABLE_FOOTNOTES)
+            .union(Options::ENABLE_TABLES)
+            .union(Options::ENABLE_TASKLISTS)
+            .union(Options::ENABLE_HEADING_ATTRIBUTES)
+    };
 
+    fn new(md: &'book str) -> Self {
         Self {
             lookahead: Default::default(),
-            parser: pulldown_cmark::Parser::new_ext(md, PARSER_OPTIONS).into_offset_iter(),
+            parser: pulldown_cmark::Parser::new_ext(md, Self::OPTIONS).into_offset_iter(),
+            html_only: Default::default(),
         }
     }
 
@@ -738,7 +1161,11 @@ impl<'book> Iterator for Parser<'book> {
     type Item = (Event<'book>, Range<usize>);
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.lookahead.pop_front().or_else(|| self.parser.next())
+        let next = self.lookahead.pop_front().or_else(|| self.parser.next());
+        if let Some((event, _)) = &next {
+            self.html_only.observe(event);
+        }
+        next
     }
 }
 
@@ -751,7 +1178,8 @@ impl<'book, 'preprocessor> PreprocessChapter<'book, 'preprocessor> {
         Self {
             preprocessor,
             chapter,
-            parser: Parser::new(&chapter.content),
+            content: chapter_content(chapter),
+            parser: Parser::new(chapter_content(chapter)),
             stack: Vec::new(),
             encountered_h1: false,
             identifiers: Default::default(),
@@ -768,6 +1196,74 @@ impl<'book, 'preprocessor> PreprocessChapter<'book, 'preprocessor> {
         self.chapter
     }
 
+    /// Whether the chapter consists only of raw HTML blocks (other than comments), which is known
+    /// once all of its events have been preprocessed.
+    pub fn is_html_only(&self) -> bool {
+        self.parser.html_only.get()
+    }
+
+    /// Prefix for identifiers in the chapter, if `id-prefix` is set.
+    pub fn id_prefix(&self) -> Option<String> {
+        let template = self.preprocessor.ctx.id_prefix?;
+        let path = self.chapter.path.as_deref()?;
+        Some(self.preprocessor.make_id_prefix(template, path))
+    }
+
+    /// Removes a GitHub-style alert marker (e.g. `[!NOTE]`) alone on the first line of the block
+    /// quote just started, returning the kind of admonition it marks.
+    fn alert_kind(&mut self) -> Option<BlockQuoteKind> {
+        let events = (self.parser)
+            .peek_until(|event| !matches!(event, Event::Start(Tag::Paragraph) | Event::Text(_)))
+            .collect::<Vec<_>>();
+        let [Event::Start(Tag::Paragraph), marker @ .., end] = &events[..] else {
+            return None;
+        };
+        let ends_paragraph = match end {
+            Event::SoftBreak | Event::HardBreak => false,
+            Event::End(TagEnd::Paragraph) => true,
+            _ => return None,
+        };
+        // The marker is split into several text events, e.g. `[`, `!NOTE`, and `]`
+        let marker = (marker.iter())
+            .map(|event| match event {
+                Event::Text(text) => &**text,
+                _ => unreachable!("only text events are peeked after the paragraph's start"),
+            })
+            .collect::<String>();
+        let kind = BlockQuoteKind::from_marker(marker.trim_end())?;
+        // Drop the marker's line, along with its paragraph if there's nothing else in it
+        let n = events.len();
+        let first = if ends_paragraph { 0 } else { 1 };
+        self.parser.lookahead.drain(first..n);
+        Some(kind)
+    }
+
+    /// Warns if the heading id `id` was already used in another chapter.
+    fn check_heading_id(&mut self, id: &str) {
+        match self.preprocessor.heading_ids.entry(id.to_owned()) {
+            hash_map::Entry::Occupied(entry) => log::warn!(
+                "Heading id '{id}' in chapter '{}' is already used in chapter '{}'",
+                self.chapter.name,
+                entry.get(),
+            ),
+            hash_map::Entry::Vacant(entry) => {
+                entry.insert(self.chapter.name.clone());
+            }
+        }
+    }
+
+    /// LaTeX label of the listing labelled `label` (e.g. `lst:main`) in the chapter, which is
+    /// scoped to the chapter so listings in different chapters can share labels.
+    pub fn listing_label(&self, label: &str) -> String {
+        match self.chapter.path.as_deref() {
+            Some(path) => format!(
+                "{}{label}",
+                self.preprocessor.make_id_prefix("{chapter}-", path)
+            ),
+            None => label.to_owned(),
+        }
+    }
+
     fn update_heading<'b>(
         &mut self,
         level: HeadingLevel,
@@ -776,18 +1272,34 @@ impl<'book, 'preprocessor> PreprocessChapter<'book, 'preprocessor> {
         const PANDOC_UNNUMBERED_CLASS: &str = "unnumbered";
         const PANDOC_UNLISTED_CLASS: &str = "unlisted";
 
-        if let HeadingLevel::H1 = level {
+        let (unnumbered, unlisted) = if let HeadingLevel::H1 = level {
             // Number the first H1 in each numbered chapter, mirroring mdBook
-            if self.encountered_h1 {
+            let first_h1 = !self.encountered_h1;
+            self.encountered_h1 = true;
+            if first_h1 {
+                let cfg = self.preprocessor.ctx.chapter_headings;
+                let heading = match self.chapter.number {
+                    Some(_) => cfg.numbered_chapters,
+                    None => cfg.unnumbered_chapters,
+                };
+                match heading {
+                    ChapterHeading::Numbered => (false, false),
+                    ChapterHeading::Unnumbered => (true, false),
+                    ChapterHeading::Unlisted => (true, true),
+                }
+            } else {
+                (true, true)
+            }
+        } else {
+            (true, true)
+        };
+        if self.preprocessor.ctx.emit_heading_classes {
+            if unnumbered {
                 classes.push(PANDOC_UNNUMBERED_CLASS.into());
+            }
+            if unlisted {
                 classes.push(PANDOC_UNLISTED_CLASS.into());
-            } else if self.chapter.number.is_none() {
-                classes.push(PANDOC_UNNUMBERED_CLASS.into());
             }
-            self.encountered_h1 = true;
-        } else {
-            classes.push(PANDOC_UNNUMBERED_CLASS.into());
-            classes.push(PANDOC_UNLISTED_CLASS.into());
         }
 
         let shift_smaller = |level| {
@@ -801,8 +1313,13 @@ impl<'book, 'preprocessor> PreprocessChapter<'book, 'preprocessor> {
                 H6 => None,
             }
         };
-        let Some(level) = iter::successors(Some(level), |level| shift_smaller(*level))
-            .nth(self.chapter.parent_names.len())
+        let shrink_by = if self.preprocessor.ctx.shrink_nested_headings {
+            self.chapter.parent_names.len()
+        } else {
+            0
+        };
+        let Some(level) =
+            iter::successors(Some(level), |level| shift_smaller(*level)).nth(shrink_by)
         else {
             log::warn!(
                 "Heading (level {level}) converted to paragraph in chapter: {}",
@@ -813,42 +1330,66 @@ impl<'book, 'preprocessor> PreprocessChapter<'book, 'preprocessor> {
         Some((level, classes))
     }
 
-    pub fn column_widths<'table>(
-        &self,
-        table: &'table str,
-    ) -> impl Iterator<Item = Option<ColWidth>> + 'table {
+    /// Widths of the `columns` columns of a table, relative to the table's width, if it is wide.
+    pub fn column_widths(&self, table: &str, columns: usize) -> Vec<Option<ColWidth>> {
         let mut wide = false;
         let mut rows = table.lines().inspect(|line| {
-            if line.len() > self.preprocessor.ctx.columns {
+            if line.len() > self.preprocessor.ctx.table_columns {
                 wide = true;
             }
         });
         // The second row of a table is the delimiter row
         // See: https://github.github.com/gfm/#tables-extension-
         let delimiter_row = rows.nth(1).expect("table did not contain a delimiter row");
-        let column_widths = || {
-            delimiter_row
-                // Cells are separated by pipes
-                .split('|')
-                .map(|cell| cell.chars().filter(char::is_ascii_punctuation).count())
-                .filter(|&width| width > 0)
-        };
+        let mut widths = delimiter_row
+            // Strip the markers of enclosing block quotes
+            .trim_start_matches(|c: char| c == '>' || c.is_whitespace())
+            // Cells are separated by pipes
+            .split('|')
+            .map(|cell| cell.chars().filter(char::is_ascii_punctuation).count())
+            .filter(|&width| width > 0)
+            .collect::<Vec<_>>();
         // Consume iterator to finish checking for long rows
         rows.for_each(|_| ());
-        let total_width = column_widths().sum::<usize>() as f64;
-        column_widths().map(move |width| wide.then(|| ColWidth(width as f64 / total_width)))
+        if widths.len() != columns {
+            log::warn!(
+                "Table delimiter row has {} columns but the table has {columns} in chapter '{}'",
+                widths.len(),
+                self.chapter.name,
+            );
+            // Missing columns are as wide as the shortest valid delimiter (`---`)
+            widths.resize(columns, 3);
+        }
+        let total_width = widths.iter().sum::<usize>() as f64;
+        (widths.into_iter())
+            .map(|width| wide.then(|| ColWidth(width as f64 / total_width)))
+            .collect()
     }
 
     fn preprocess(mut self, writer: impl io::Write) -> anyhow::Result<()> {
         let mut tree = TreeBuilder::new();
         while let Some((event, range)) = self.parser.next() {
             self.preprocess_event(event, range.clone(), &mut tree)
-                .with_context(|| {
-                    format!("failed to preprocess '{}'", &self.chapter.content[range])
-                })?;
+                .with_context(|| format!("failed to preprocess '{}'", &self.content[range]))?;
         }
         let events = tree.finish();
 
+        // Dump trees to the build directory, since the preprocessed directory may not be kept
+        if self.preprocessor.ctx.dump_tree {
+            if let Some(path) = &self.chapter.source_path {
+                let path = (self.preprocessor.ctx.destination.join("trees"))
+                    .join(path)
+                    .with_extension("tree");
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent).with_context(|| {
+                        format!("Unable to create directory: {}", parent.display())
+                    })?;
+                }
+                fs::write(&path, format!("{events:#?}\n"))
+                    .with_context(|| format!("Unable to write file: {}", path.display()))?;
+            }
+        }
+
         log::trace!("Writing Pandoc AST for chapter '{}'", self.chapter.name);
         pandoc::native::Serializer::serialize(writer, self, |blocks| events.emit(blocks))
     }
@@ -892,7 +1433,7 @@ impl<'book, 'preprocessor> PreprocessChapter<'book, 'preprocessor> {
                         tree,
                         MdElement::Table {
                             alignment,
-                            source: &self.chapter.content[range],
+                            source: &self.content[range],
                         },
                     ),
                     Tag::TableHead => {
@@ -911,21 +1452,51 @@ impl<'book, 'preprocessor> PreprocessChapter<'book, 'preprocessor> {
                         attrs,
                     } => {
                         let id = Some(match id {
-                            Some(id) => id,
+                            Some(id) => {
+                                self.check_heading_id(&id);
+                                id
+                            }
                             None => {
-                                let mut id =
-                                    Preprocessor::make_gfm_identifier(self.parser.peek_until(
-                                        |event| matches!(event, Event::End(TagEnd::Heading(..))),
-                                    ));
+                                let mut id = Preprocessor::make_identifier(
+                                    self.preprocessor.ctx.heading_ids,
+                                    self.parser.peek_until(|event| {
+                                        matches!(event, Event::End(TagEnd::Heading(..)))
+                                    }),
+                                );
                                 if let Some(count) = self.identifiers.get_mut(&id) {
                                     write!(id, "-{}", count.get()).unwrap();
                                     *count = count.saturating_add(1);
                                 } else {
                                     self.identifiers.insert(id.clone(), NonZeroU32::MIN);
                                 }
+                                // A prefix shared by all chapters doesn't keep identifiers generated
+                                // in different chapters apart
+                                if (self.preprocessor.ctx.id_prefix)
+                                    .is_some_and(|template| !template.contains("{chapter}"))
+                                {
+                                    self.check_heading_id(&id);
+                                }
                                 id.into()
                             }
                         });
+                        // Title the chapter's first heading with its name from `SUMMARY.md` if
+                        // that's configured and the heading's own text is different
+                        let retitle = self.preprocessor.ctx.chapter_headings.text
+                            == ChapterHeadingText::Summary
+                            && level == HeadingLevel::H1
+                            && !self.encountered_h1
+                            && {
+                                let text = (self.parser)
+                                    .peek_until(|event| {
+                                        matches!(event, Event::End(TagEnd::Heading(..)))
+                                    })
+                                    .filter_map(|event| match event {
+                                        Event::Text(text) | Event::Code(text) => Some(&**text),
+                                        _ => None,
+                                    })
+                                    .collect::<String>();
+                                text != self.chapter.name
+                            };
                         let element = self
                             .update_heading(level, classes)
                             .map(|(level, classes)| MdElement::Heading {
@@ -935,7 +1506,21 @@ impl<'book, 'preprocessor> PreprocessChapter<'book, 'preprocessor> {
                                 attrs,
                             })
                             .unwrap_or(MdElement::Paragraph);
-                        push_element(self, tree, element)
+                        let node = push_element(self, tree, element)?;
+                        if retitle {
+                            // Replace the heading's contents, leaving its end for the caller
+                            while let Some((event, range)) = self.parser.next() {
+                                if let Event::End(TagEnd::Heading(..)) = event {
+                                    self.parser.lookahead.push_front((event, range));
+                                    break;
+                                }
+                            }
+                            tree.create_element(MdElement::Text(
+                                self.chapter.name.as_str().into(),
+                            ))?;
+                            tree.process_html("</span>".into());
+                        }
+                        Ok(node)
                     }
                     Tag::Link {
                         link_type,
@@ -952,7 +1537,10 @@ impl<'book, 'preprocessor> PreprocessChapter<'book, 'preprocessor> {
                         push_element(self, tree, MdElement::Link { dest_url, title })
                     }
                     Tag::Paragraph => push_element(self, tree, MdElement::Paragraph),
-                    Tag::BlockQuote => push_element(self, tree, MdElement::BlockQuote),
+                    Tag::BlockQuote => {
+                        let kind = self.alert_kind();
+                        push_element(self, tree, MdElement::BlockQuote(kind))
+                    }
                     Tag::CodeBlock(kind) => push_element(self, tree, MdElement::CodeBlock(kind)),
                     Tag::Emphasis => push_element(self, tree, MdElement::Emphasis),
                     Tag::Strong => push_element(self, tree, MdElement::Strong),
@@ -1020,6 +1608,7 @@ impl<'book, 'preprocessor> PreprocessChapter<'book, 'preprocessor> {
                 Ok(())
             }
             Event::Text(text) => {
+                let text = sanitize(text, self.preprocessor.ctx.sanitize);
                 tree.create_element(MdElement::Text(text))?;
                 tree.process_html("</span>".into());
                 Ok(())
@@ -1038,7 +1627,7 @@ impl<'book, 'preprocessor> PreprocessChapter<'book, 'preprocessor> {
                 Ok(())
             }
             Event::HardBreak => {
-                tree.process_html("<br>".into());
+                tree.create_element(MdElement::HardBreak)?;
                 Ok(())
             }
             Event::Rule => {
@@ -1056,7 +1645,14 @@ impl<'book, 'preprocessor> PreprocessChapter<'book, 'preprocessor> {
         &mut self,
         dest_url: CowStr<'url>,
         link_type: LinkType,
-    ) -> Result<CowStr<'url>, UnresolvableRemoteImage> {
+    ) -> Result<CowStr<'url>, UnresolvableImage> {
+        if dest_url.trim().is_empty() {
+            log::warn!(
+                "Image in chapter '{}' has an empty destination; rendering its alt text instead",
+                self.chapter.name,
+            );
+            return Err(UnresolvableImage);
+        }
         let resolved = match self.chapter.path.as_ref() {
             None => Err((anyhow!("chapter has no path"), dest_url)),
             Some(chapter_path) => {
@@ -1078,25 +1674,152 @@ impl<'book, 'preprocessor> PreprocessChapter<'book, 'preprocessor> {
                     self.chapter.name,
                 );
                 if let Some(UnresolvableRemoteImageError { .. }) = err.downcast_ref() {
-                    Err(UnresolvableRemoteImage)
+                    Err(UnresolvableImage)
                 } else {
                     Ok(link)
                 }
             }
         }
     }
+
+    /// Copies a local file referenced by a raw HTML attribute (e.g. `<video src="...">`) to the
+    /// preprocessed directory if it hasn't been copied already.
+    pub fn copy_html_asset(&mut self, url: &str) {
+        if self.preprocessor.ctx.copy_assets == CopyAssets::All {
+            return;
+        }
+        let Some(chapter_path) = &self.chapter.path else {
+            return;
+        };
+        let path = &url[..url.find(['?', '#']).unwrap_or(url.len())];
+        // Skip URLs with schemes and network-path references
+        if path.is_empty() || path.contains(':') || path.starts_with("//") {
+            return;
+        }
+        let source_dir = &self.preprocessor.ctx.book.source_dir;
+        let path = match Path::new(path).strip_prefix("/") {
+            Ok(relative_to_root) => source_dir.join(relative_to_root),
+            Err(_) => source_dir.join(chapter_path.parent().unwrap()).join(path),
+        };
+        let Ok(normalized) = self.preprocessor.normalize_path(&path) else {
+            return;
+        };
+        if !normalized.src_absolute_path.is_file() || normalized.exists().unwrap_or(true) {
+            return;
+        }
+        if let Err(err) = normalized.copy_to_preprocessed() {
+            log::warn!(
+                "Unable to copy '{url}' referenced in chapter '{}': {err:#}",
+                self.chapter.name,
+            );
+        }
+    }
+
+    /// Writes an inline SVG image to a file in the preprocessed directory, returning the file's
+    /// path relative to the book's root.
+    pub fn write_svg(&mut self, svg: &[u8]) -> anyhow::Result<PathBuf> {
+        // Name the file after a stable hash so the same SVG keeps its path across builds
+        let filename = format!("{:x}.svg", Sha256::digest(svg));
+        let path = self.preprocessor.preprocessed.join(&filename);
+        fs::write(&path, svg)
+            .with_context(|| format!("Unable to write SVG image to file '{}'", path.display()))?;
+        Ok(self
+            .preprocessor
+            .preprocessed_relative_to_root
+            .join(filename))
+    }
+
+    /// Renders a Graphviz graph to a file in the given format (e.g. `svg`) with `dot`, returning
+    /// the file's path relative to the book's root. Rendered graphs are cached across builds.
+    pub fn render_graphviz(&self, source: &str, format: &str) -> anyhow::Result<PathBuf> {
+        let cache = self.preprocessor.ctx.cache;
+        cache.get_or_create("graphviz", source, format, |path| {
+            let mut dot = process::Command::new("dot")
+                .arg(format!("-T{format}"))
+                .arg("-o")
+                .arg(path)
+                .stdin(process::Stdio::piped())
+                .stderr(process::Stdio::piped())
+                .spawn()
+                .context("Unable to run `dot`")?;
+            (dot.stdin.take().expect("stdin should be piped"))
+                .write_all(source.as_bytes())
+                .context("Unable to write graph to `dot`")?;
+            let output = dot.wait_with_output().context("Unable to run `dot`")?;
+            anyhow::ensure!(
+                output.status.success(),
+                "`dot` exited with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            Ok(())
+        })
+    }
+}
+
+/// Returns a chapter's content without any leading UTF-8 byte-order mark, which would otherwise
+/// be parsed as text at the start of the chapter.
+fn chapter_content(chapter: &Chapter) -> &str {
+    (chapter.content.strip_prefix('\u{feff}')).unwrap_or(&chapter.content)
+}
+
+/// Strips or normalizes the kinds of characters in `kinds` in `text`.
+fn sanitize<'a>(text: CowStr<'a>, kinds: &[Sanitize]) -> CowStr<'a> {
+    let replacement = |c| kinds.iter().find_map(|kind| kind.replacement(c));
+    if !text.chars().any(|c| replacement(c).is_some()) {
+        return text;
+    }
+    let mut sanitized = String::with_capacity(text.len());
+    for c in text.chars() {
+        match replacement(c) {
+            Some(replacement) => sanitized.push_str(replacement),
+            None => sanitized.push(c),
+        }
+    }
+    sanitized.into()
+}
+
+/// Tracks whether a chapter's events consist only of raw HTML blocks (other than comments), in
+/// which case the chapter is given a [`CHAPTER_START_ANCHOR`].
+#[derive(Default)]
+struct HtmlOnly {
+    depth: usize,
+    has_html: bool,
+    has_other_blocks: bool,
+}
+
+impl HtmlOnly {
+    fn observe(&mut self, event: &Event<'_>) {
+        match event {
+            Event::Start(tag) => {
+                if self.depth == 0 && !matches!(tag, Tag::HtmlBlock) {
+                    self.has_other_blocks = true;
+                }
+                self.depth += 1;
+            }
+            Event::End(_) => self.depth -= 1,
+            Event::Html(html) => self.has_html |= !html.trim_start().starts_with("<!--"),
+            _ => {}
+        }
+    }
+
+    fn get(&self) -> bool {
+        self.has_html && !self.has_other_blocks
+    }
 }
 
 impl<'book> ChapterAnchors<'book> {
     /// Searches for tags in the provided chapter with identifiers that can be used as link anchors.
-    fn new(chapter: &'book Chapter) -> anyhow::Result<Self> {
-        use pulldown_cmark::{Options, Parser};
-        let mut parser = Parser::new_ext(&chapter.content, Options::ENABLE_HEADING_ATTRIBUTES);
+    fn new(chapter: &'book Chapter, heading_ids: HeadingIds) -> anyhow::Result<Self> {
+        // Parse with the same extensions as the chapter itself so generated identifiers match
+        let mut parser = pulldown_cmark::Parser::new_ext(chapter_content(chapter), Parser::OPTIONS);
+        let mut html_only = HtmlOnly::default();
         let beginning = 'beginning: {
             let heading_id = loop {
                 let Some(event) = parser.next() else {
-                    break 'beginning None;
+                    break 'beginning html_only.get().then(|| CHAPTER_START_ANCHOR.into());
                 };
+                html_only.observe(&event);
                 if let Event::Start(Tag::Heading { id, .. }) = event {
                     break id;
                 }
@@ -1104,7 +1827,7 @@ impl<'book> ChapterAnchors<'book> {
             Some(heading_id.unwrap_or_else(|| {
                 let heading_contents =
                     parser.take_while(|event| !matches!(event, Event::End(TagEnd::Heading(_))));
-                Preprocessor::make_gfm_identifier(heading_contents).into()
+                Preprocessor::make_identifier(heading_ids, heading_contents).into()
             }))
         };
         if beginning.is_none() {
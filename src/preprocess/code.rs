@@ -1,58 +1,165 @@
-use std::{borrow::Cow, iter, str};
+use std::{borrow::Cow, collections::HashSet, iter, str};
 
 use pulldown_cmark::CodeBlockKind;
 
-use crate::CodeConfig;
+use crate::{CodeConfig, UnknownLanguage};
+
+/// Prefix of the identifiers labelling code blocks as listings that can be referenced.
+pub const LISTING_LABEL_PREFIX: &str = "lst:";
+
+/// Languages of console sessions, i.e. shell prompts interleaved with their output.
+const CONSOLE_LANGUAGES: &[&str] = &["console", "shell-session", "shellsession"];
 
 pub enum CodeBlock<'book> {
-    Rust,
+    Rust {
+        filename: Option<&'book str>,
+        label: Option<&'book str>,
+    },
     Other {
         language: Option<&'book str>,
         hidelines_prefix: Option<&'book str>,
+        filename: Option<&'book str>,
+        label: Option<&'book str>,
     },
 }
 
 impl<'book> CodeBlock<'book> {
-    pub fn new(kind: &'book CodeBlockKind<'_>, cfg: Option<&'book mdbook::config::Code>) -> Self {
+    pub fn new(
+        kind: &'book CodeBlockKind<'_>,
+        cfg: Option<&'book mdbook::config::Code>,
+        code: &'book CodeConfig,
+    ) -> Self {
         // MdBook supports custom attributes in code block info strings.
         // Attributes are separated by a comma, space, or tab from the language name.
         // See https://rust-lang.github.io/mdBook/format/mdbook.html#rust-code-block-attributes
         // This processes and strips out the attributes.
-        let (language, mut attributes) = {
+        let (language, attributes) = {
             let info_string = match kind {
                 CodeBlockKind::Indented => "",
                 CodeBlockKind::Fenced(info_string) => info_string,
             };
-            let mut parts = info_string.split([',', ' ', '\t']).map(|part| part.trim());
-            (parts.next(), parts)
+            let mut parts = split_info_string(info_string).map(|part| part.trim());
+            (parts.next(), parts.collect::<Vec<_>>())
+        };
+        let attribute = |name: &str| {
+            (attributes.iter().copied()).find_map(|attr| attr.strip_prefix(name)?.strip_prefix('='))
         };
 
+        let language = language.map(|language| match code.language_aliases.get(language) {
+            Some(alias) => alias.as_str(),
+            None if CONSOLE_LANGUAGES.contains(&language) => {
+                code.console_language.as_deref().unwrap_or(language)
+            }
+            None => language,
+        });
+
+        let filename = (attribute("filename").or_else(|| attribute("title")))
+            .map(|filename| filename.trim_matches('"'))
+            .filter(|filename| !filename.is_empty());
+        // Listings are labelled like with pandoc-crossref, e.g. `{#lst:example}`
+        let label = (attributes.iter().copied())
+            .filter_map(|attr| attr.strip_prefix("{#")?.strip_suffix('}'))
+            .find(|id| id.starts_with(LISTING_LABEL_PREFIX));
         match language {
-            Some("rust") => Self::Rust,
+            Some("rust") => Self::Rust { filename, label },
             language => {
-                let hidelines_override =
-                    attributes.find_map(|attr| attr.strip_prefix("hidelines="));
-                let hidelines_prefix = hidelines_override.or_else(|| {
+                let hidelines_prefix = attribute("hidelines").or_else(|| {
                     // Respect [output.html.code.hidelines]
                     Some(cfg?.hidelines.get(language?)?.as_str())
                 });
                 Self::Other {
                     language,
                     hidelines_prefix,
+                    filename,
+                    label,
                 }
             }
         }
     }
 }
 
+/// Splits a code block's info string at commas, spaces, and tabs, except within double-quoted
+/// attribute values like `title="My File"`.
+fn split_info_string(info_string: &str) -> impl Iterator<Item = &str> {
+    let mut quoted = false;
+    let mut start = 0;
+    let mut chars = info_string.char_indices();
+    iter::from_fn(move || {
+        if start > info_string.len() {
+            return None;
+        }
+        let rest = &info_string[start..];
+        for (idx, c) in chars.by_ref() {
+            match c {
+                '"' => quoted = !quoted,
+                ',' | ' ' | '\t' if !quoted => {
+                    let part = &info_string[start..idx];
+                    start = idx + 1;
+                    return Some(part);
+                }
+                _ => {}
+            }
+        }
+        start = info_string.len() + 1;
+        Some(rest)
+    })
+}
+
 impl CodeBlock<'_> {
     pub fn language(&self) -> Option<&str> {
         match self {
-            Self::Rust => Some("rust"),
+            Self::Rust { .. } => Some("rust"),
             Self::Other { language, .. } => *language,
         }
     }
 
+    /// The name of the file the code belongs to, from a `filename` or `title` attribute.
+    pub fn filename(&self) -> Option<&str> {
+        match self {
+            Self::Rust { filename, .. } | Self::Other { filename, .. } => *filename,
+        }
+    }
+
+    /// The label of the code block as a numbered listing, from a `{#lst:...}` attribute.
+    pub fn label(&self) -> Option<&str> {
+        match self {
+            Self::Rust { label, .. } | Self::Other { label, .. } => *label,
+        }
+    }
+
+    /// Applies [`CodeConfig::unknown_language`] if Pandoc can't highlight the code block's
+    /// language, returning `None` if the code block should be omitted.
+    pub fn handle_unknown_language(
+        self,
+        cfg: &CodeConfig,
+        known_languages: Option<&HashSet<String>>,
+    ) -> Option<Self> {
+        let (Some(known_languages), Some(language)) = (known_languages, self.language()) else {
+            return Some(self);
+        };
+        if known_languages.contains(&language.to_lowercase()) {
+            return Some(self);
+        }
+        match cfg.unknown_language {
+            UnknownLanguage::Passthrough => Some(self),
+            UnknownLanguage::Text => match self {
+                Self::Rust { filename, label } => Some(Self::Rust { filename, label }),
+                Self::Other {
+                    hidelines_prefix,
+                    filename,
+                    label,
+                    ..
+                } => Some(Self::Other {
+                    language: None,
+                    hidelines_prefix,
+                    filename,
+                    label,
+                }),
+            },
+            UnknownLanguage::Drop => None,
+        }
+    }
+
     pub fn lines<'code>(
         &self,
         code: impl Iterator<Item = &'code str>,
@@ -91,8 +198,8 @@ impl CodeBlock<'_> {
         });
 
         // https://rust-lang.github.io/mdBook/format/mdbook.html#hiding-code-lines
-        match self {
-            Self::Rust => lines
+        let mut lines: Vec<_> = match self {
+            Self::Rust { .. } => lines
                 .filter_map(|line| Self::displayed_rust_line(line, cfg))
                 .collect(),
             Self::Other {
@@ -119,7 +226,18 @@ impl CodeBlock<'_> {
                     lines.map(Cow::Borrowed).collect()
                 }
             }
+        };
+
+        if cfg.trim_blank_lines {
+            let is_blank = |line: &Cow<'_, str>| line.trim().is_empty();
+            let end = lines.iter().rposition(|line| !is_blank(line));
+            lines.truncate(end.map_or(0, |end| end + 1));
+            let start = lines.iter().position(|line| !is_blank(line));
+            let start = start.unwrap_or(lines.len());
+            lines.drain(..start);
         }
+
+        lines
     }
 
     fn displayed_rust_line<'line>(line: &'line str, cfg: &CodeConfig) -> Option<Cow<'line, str>> {
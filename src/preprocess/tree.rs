@@ -3,6 +3,7 @@ use std::{
     collections::HashMap,
     fmt,
     io::{self, Write},
+    iter,
 };
 
 use aho_corasick::AhoCorasick;
@@ -12,15 +13,21 @@ use html5ever::{
     expanded_name, local_name, namespace_url, ns,
     serialize::Serializer,
     tendril::{fmt::UTF8, format_tendril, StrTendril, Tendril, TendrilSink},
-    LocalName,
+    LocalName, QualName,
 };
 use indexmap::IndexSet;
-use pulldown_cmark::{CowStr, LinkType};
+use once_cell::sync::Lazy;
+use pulldown_cmark::{Alignment, CodeBlockKind, CowStr, LinkType};
+use regex::Regex;
 
-use crate::{html, latex, pandoc, preprocess::UnresolvableRemoteImage};
+use crate::{
+    html, latex, pandoc,
+    preprocess::{UnresolvableImage, CHAPTER_START_ANCHOR},
+    HardBreak, HorizontalRule, ListSpacing, SvgInLatex, TaskListStyle,
+};
 
 mod node;
-pub use node::{Attributes, Element, MdElement, Node, QualNameExt};
+pub use node::{Attributes, BlockQuoteKind, Element, HtmlElement, MdElement, Node, QualNameExt};
 
 mod sink;
 pub use sink::HtmlTreeSink;
@@ -132,11 +139,29 @@ impl<'book> Emitter<'book> {
         serializer: &mut pandoc::native::SerializeNested<'_, '_, 'book, '_, impl io::Write>,
     ) -> anyhow::Result<()> {
         log::trace!("Writing Pandoc AST for {:?}", node.value());
+        let ctx = &serializer.preprocessor().preprocessor.ctx;
+        if ctx.keyboard_shortcuts
+            && matches!(ctx.output, pandoc::OutputFormat::Latex { .. })
+            && continues_shortcut(node)
+        {
+            // Rendered along with the shortcut's first key
+            return Ok(());
+        }
         match node.value() {
             Node::Document => unreachable!(),
             Node::HtmlComment(comment) => {
                 serializer.serialize_raw_html(|serializer| serializer.write_comment(comment))
             }
+            Node::HtmlText(text)
+                if ctx.trim_html_whitespace
+                    && !matches!(ctx.output, pandoc::OutputFormat::HtmlLike)
+                    && text.trim().is_empty()
+                    && serializer.is_between_blocks()
+                    && node.next_sibling().map_or(true, |next| !is_inline(next)) =>
+            {
+                // Whitespace between blocks would otherwise become a `Plain` block of its own
+                Ok(())
+            }
             Node::HtmlText(text) => {
                 if matches!(
                     serializer.preprocessor().preprocessor.ctx.output,
@@ -150,53 +175,199 @@ impl<'book> Emitter<'book> {
                 }
             }
             Node::Element(Element::Markdown(element)) => match element {
-                MdElement::Paragraph => {
-                    serializer
-                        .blocks()?
-                        .serialize_element()?
-                        .serialize_para(|serializer| {
-                            serializer.serialize_nested(|serializer| {
-                                for node in node.children() {
-                                    self.serialize_node(node, serializer)?;
-                                }
-                                Ok(())
-                            })
+                MdElement::Paragraph
+                    if serializer.preprocessor().preprocessor.ctx.list_spacing
+                        == ListSpacing::Tight
+                        && node.parent().is_some_and(|parent| {
+                            matches!(
+                                parent.value(),
+                                Node::Element(Element::Markdown(MdElement::Item))
+                            )
+                        }) =>
+                {
+                    serializer.serialize_inlines(|inlines| {
+                        inlines.serialize_nested(|serializer| {
+                            self.serialize_children(node, serializer)
                         })
+                    })?;
+                    // End the `Plain` block so following paragraphs aren't merged into it
+                    serializer.blocks().map(|_| ())
+                }
+                MdElement::Paragraph
+                    if serializer.preprocessor().preprocessor.ctx.alt_as_caption
+                        && matches!(
+                            serializer.preprocessor().preprocessor.ctx.output,
+                            pandoc::OutputFormat::Latex { .. }
+                        )
+                        && captionable_image(node).is_some() =>
+                {
+                    let image = captionable_image(node).unwrap();
+                    serializer.blocks()?.serialize_element()?.serialize_figure(
+                        (),
+                        |caption| {
+                            caption.serialize_nested(|serializer| {
+                                self.serialize_children(image, serializer)
+                            })
+                        },
+                        |blocks| {
+                            blocks.serialize_nested(|serializer| {
+                                self.serialize_node(image, serializer)
+                            })
+                        },
+                    )
+                }
+                MdElement::Paragraph
+                    if serializer.preprocessor().preprocessor.ctx.lead_paragraph
+                        && is_lead_paragraph(node) =>
+                {
+                    if let pandoc::OutputFormat::Latex { .. } =
+                        serializer.preprocessor().preprocessor.ctx.output
+                    {
+                        Self::serialize_raw_latex_block(r"\begin{em}", serializer)?;
+                        self.serialize_paragraph(node, serializer)?;
+                        return Self::serialize_raw_latex_block(r"\end{em}", serializer);
+                    }
+                    serializer.blocks()?.serialize_element()?.serialize_div(
+                        (None, &[CowStr::Borrowed("lead")], &[]),
+                        |blocks| {
+                            blocks.serialize_nested(|serializer| {
+                                self.serialize_paragraph(node, serializer)
+                            })
+                        },
+                    )
+                }
+                MdElement::Paragraph => self.serialize_paragraph(node, serializer),
+                MdElement::Text(text) => {
+                    let ctx = &mut serializer.preprocessor().preprocessor.ctx;
+                    if ctx.index && INDEX_MARKER.is_match(text) {
+                        // Replace index markers with index entries in LaTeX and drop them otherwise
+                        let is_latex = match &mut ctx.output {
+                            pandoc::OutputFormat::Latex { packages } => {
+                                packages.need(latex::Package::MakeIdx);
+                                true
+                            }
+                            pandoc::OutputFormat::HtmlLike | pandoc::OutputFormat::Other => false,
+                        };
+                        return serializer.serialize_inlines(|inlines| {
+                            let mut last = 0;
+                            for captures in INDEX_MARKER.captures_iter(text) {
+                                let marker = captures.get(0).unwrap();
+                                if marker.start() > last {
+                                    inlines
+                                        .serialize_element()?
+                                        .serialize_str(&text[last..marker.start()])?;
+                                }
+                                if is_latex {
+                                    inlines.serialize_element()?.serialize_raw_inline(
+                                        "latex",
+                                        |raw| {
+                                            let term =
+                                                latex::escape_index_entry(captures[1].trim());
+                                            write!(raw, r"\index{{{term}}}")
+                                        },
+                                    )?;
+                                }
+                                last = marker.end();
+                            }
+                            if last < text.len() {
+                                inlines.serialize_element()?.serialize_str(&text[last..])?;
+                            }
+                            Ok(())
+                        });
+                    }
+                    serializer.serialize_inlines(|inlines| {
+                        inlines.serialize_element()?.serialize_str(text)
+                    })
                 }
-                MdElement::Text(text) => serializer
-                    .serialize_inlines(|inlines| inlines.serialize_element()?.serialize_str(text)),
                 MdElement::SoftBreak => serializer.serialize_inlines(|inlines| {
                     inlines.serialize_element()?.serialize_soft_break()
                 }),
+                MdElement::HardBreak => {
+                    let ctx = &serializer.preprocessor().preprocessor.ctx;
+                    // Write hard breaks in Markdown output as raw Markdown to control their syntax
+                    let raw = match ctx.hard_break {
+                        HardBreak::Linebreak => None,
+                        HardBreak::Backslash => Some("\\\n"),
+                        HardBreak::Spaces => Some("  \n"),
+                    };
+                    match (ctx.markdown_format.clone(), raw) {
+                        (Some(format), Some(raw)) => serializer.serialize_inlines(|inlines| {
+                            (inlines.serialize_element()?)
+                                .serialize_raw_inline(&format, |writer| write!(writer, "{raw}"))
+                        }),
+                        _ => serializer.serialize_inlines(|inlines| {
+                            inlines.serialize_element()?.serialize_line_break()
+                        }),
+                    }
+                }
                 MdElement::Heading {
                     level,
                     id,
                     classes,
                     attrs,
-                } => serializer.blocks()?.serialize_element()?.serialize_header(
-                    *level as usize,
-                    (id.as_deref(), classes, attrs),
-                    |inlines| {
-                        inlines.serialize_nested(|serializer| {
-                            for node in node.children() {
-                                self.serialize_node(node, serializer)?;
-                            }
-                            Ok(())
-                        })
-                    },
-                ),
+                } => {
+                    let ctx = &serializer.preprocessor().preprocessor.ctx;
+                    if ctx.page_break_before.contains(&(*level as usize)) {
+                        serializer
+                            .blocks()?
+                            .serialize_element()?
+                            .serialize_raw_block("latex", |raw| {
+                                raw.serialize_code(r"\clearpage")
+                            })?;
+                    }
+                    serializer.blocks()?.serialize_element()?.serialize_header(
+                        *level as usize,
+                        (id.as_deref(), classes, attrs),
+                        |inlines| {
+                            inlines.serialize_nested(|serializer| {
+                                for node in node.children() {
+                                    self.serialize_node(node, serializer)?;
+                                }
+                                Ok(())
+                            })
+                        },
+                    )
+                }
+                MdElement::List(_)
+                    if matches!(
+                        serializer.preprocessor().preprocessor.ctx.output,
+                        pandoc::OutputFormat::Latex { .. }
+                    ) && list_depth(node) > latex::MAX_LIST_DEPTH =>
+                {
+                    // Flatten lists nested too deeply for LaTeX into their parent items
+                    self.serialize_children(node, serializer)
+                }
+                MdElement::List(None)
+                    if is_checklist(node, &serializer.preprocessor().preprocessor.ctx) =>
+                {
+                    if let pandoc::OutputFormat::Latex { packages } =
+                        &mut serializer.preprocessor().preprocessor.ctx.output
+                    {
+                        packages.need(latex::Package::EnumItem);
+                    }
+                    Self::serialize_raw_latex_block(
+                        r"\begin{itemize}[label=$\square$, leftmargin=*]",
+                        serializer,
+                    )?;
+                    for item in node.children() {
+                        let label = match task_list_marker(item) {
+                            Some(true) => r"\item[$\boxtimes$]",
+                            Some(false) => r"\item",
+                            // Items that aren't tasks get no checkbox
+                            None => r"\item[]",
+                        };
+                        Self::serialize_raw_latex_block(label, serializer)?;
+                        self.serialize_list_item(item, serializer)?;
+                    }
+                    Self::serialize_raw_latex_block(r"\end{itemize}", serializer)
+                }
                 MdElement::List(None) => serializer
                     .blocks()?
                     .serialize_element()?
                     .serialize_bullet_list(|items| {
                         for child in node.children() {
                             let mut item = items.serialize_element()??;
-                            item.serialize_nested(|item| {
-                                for node in child.children() {
-                                    self.serialize_node(node, item)?;
-                                }
-                                Ok(())
-                            })?;
+                            item.serialize_nested(|item| self.serialize_list_item(child, item))?;
                             item.finish()?;
                         }
                         Ok(())
@@ -207,23 +378,100 @@ impl<'book> Emitter<'book> {
                     .serialize_ordered_list(*first, |items| {
                         for child in node.children() {
                             let mut item = items.serialize_element()??;
-                            item.serialize_nested(|item| {
-                                for node in child.children() {
-                                    self.serialize_node(node, item)?;
-                                }
-                                Ok(())
-                            })?;
+                            item.serialize_nested(|item| self.serialize_list_item(child, item))?;
                             item.finish()?;
                         }
                         Ok(())
                     }),
                 MdElement::Item => self.serialize_children(node, serializer),
+                MdElement::TaskListMarker(_)
+                    if (node.ancestors())
+                        .find(|node| {
+                            matches!(
+                                node.value(),
+                                Node::Element(Element::Markdown(MdElement::List(_)))
+                            )
+                        })
+                        .is_some_and(|list| {
+                            is_checklist(list, &serializer.preprocessor().preprocessor.ctx)
+                        }) =>
+                {
+                    // Rendered as the item's label instead
+                    Ok(())
+                }
                 MdElement::TaskListMarker(checked) => serializer.serialize_inlines(|inlines| {
                     inlines
                         .serialize_element()?
                         .serialize_str_unescaped(if *checked { "\\9746" } else { "\\9744" })?;
                     inlines.serialize_element()?.serialize_space()
                 }),
+                MdElement::Link { dest_url, .. }
+                    if matches!(
+                        serializer.preprocessor().preprocessor.ctx.output,
+                        pandoc::OutputFormat::Latex { .. }
+                    ) && listing_reference(
+                        dest_url,
+                        serializer.preprocessor().id_prefix().as_deref(),
+                    )
+                    .is_some() =>
+                {
+                    // Refer to listings by number, since they have no anchor for Pandoc to link to
+                    let preprocessor = serializer.preprocessor();
+                    let id_prefix = preprocessor.id_prefix();
+                    let label = listing_reference(dest_url, id_prefix.as_deref())
+                        .expect("link should refer to a listing");
+                    let label = preprocessor.listing_label(label);
+                    serializer.serialize_inlines(|inlines| {
+                        if !node.has_children() {
+                            return inlines
+                                .serialize_element()?
+                                .serialize_raw_inline("latex", |raw| {
+                                    write!(raw, r"\ref{{{label}}}")
+                                });
+                        }
+                        inlines
+                            .serialize_element()?
+                            .serialize_raw_inline("latex", |raw| {
+                                write!(raw, r"\hyperref[{label}]{{")
+                            })?;
+                        inlines
+                            .serialize_nested(|inlines| self.serialize_children(node, inlines))?;
+                        inlines
+                            .serialize_element()?
+                            .serialize_raw_inline("latex", |raw| write!(raw, "}}"))
+                    })
+                }
+                MdElement::Link { dest_url, title }
+                    if serializer.preprocessor().preprocessor.ctx.url_style
+                        == pandoc::UrlStyle::Footnote
+                        && matches!(
+                            serializer.preprocessor().preprocessor.ctx.output,
+                            pandoc::OutputFormat::Latex { .. }
+                        )
+                        && (dest_url.starts_with("http://")
+                            || dest_url.starts_with("https://"))
+                        && text_content(node) != **dest_url =>
+                {
+                    // Keep the link's text in the body, printing its URL in a footnote
+                    serializer.serialize_inlines(|inlines| {
+                        inlines.serialize_element()?.serialize_link(
+                            (None, &[], &[]),
+                            |alt| alt.serialize_nested(|alt| self.serialize_children(node, alt)),
+                            dest_url,
+                            title,
+                        )?;
+                        inlines.serialize_element()?.serialize_note(|blocks| {
+                            blocks.serialize_element()?.serialize_para(|inlines| {
+                                inlines.serialize_element()?.serialize_link(
+                                    (),
+                                    |text| text.serialize_element()?.serialize_str(dest_url),
+                                    dest_url,
+                                    "",
+                                )
+                            })
+                        })
+                    })
+                }
                 MdElement::Link { dest_url, title } => serializer.serialize_inlines(|inlines| {
                     inlines.serialize_element()?.serialize_link(
                         (None, &[], &[]),
@@ -233,8 +481,6 @@ impl<'book> Emitter<'book> {
                     )
                 }),
                 MdElement::Table { alignment, source } => {
-                    let preprocessor = serializer.preprocessor();
-                    let column_widths = preprocessor.column_widths(source);
                     let mut children = node.children();
                     let (head, body) = (children.next().unwrap(), children.next().unwrap());
                     debug_assert!(children.next().is_none());
@@ -256,9 +502,25 @@ impl<'book> Emitter<'book> {
                         event => anyhow::bail!("expected table body, got {event:?}"),
                     };
 
+                    // The header row determines the table's number of columns
+                    let columns = (head.children())
+                        .find(|row| is_html_element(*row, local_name!("tr")))
+                        .map_or(alignment.len(), |row| row.children().count());
+                    if alignment.len() != columns {
+                        log::warn!(
+                            "Table has {} column alignments but {columns} columns in chapter '{}'",
+                            alignment.len(),
+                            serializer.preprocessor().chapter().name,
+                        );
+                    }
+                    let alignment = (alignment.iter().copied().map(Into::into))
+                        .chain(iter::repeat_with(|| pandoc::native::Alignment::Default))
+                        .take(columns);
+                    let column_widths = serializer.preprocessor().column_widths(source, columns);
+
                     serializer.blocks()?.serialize_element()?.serialize_table(
                         (),
-                        (alignment.iter().copied().map(Into::into)).zip(column_widths),
+                        alignment.zip(column_widths),
                         (&thead.attrs, |serializer| {
                             for row in head.children() {
                                 match row.value() {
@@ -270,6 +532,7 @@ impl<'book> Emitter<'book> {
                                                         for node in cell.children() {
                                                             cells.serialize_element()?.serialize_cell(
                                                                 &element.attrs,
+                                                                pandoc::native::Alignment::Default,
                                                                 |blocks| {
                                                                     blocks.serialize_nested(|serializer| {
                                                                         self.serialize_node(
@@ -310,7 +573,7 @@ impl<'book> Emitter<'book> {
                                                         {
                                                             cells
                                                                 .serialize_element()?
-                                                                .serialize_cell(&element.attrs, |blocks| {
+                                                                .serialize_cell(&element.attrs, pandoc::native::Alignment::Default, |blocks| {
                                                                     blocks.serialize_nested(
                                                                         |serializer| {
                                                                             for node in
@@ -378,7 +641,7 @@ impl<'book> Emitter<'book> {
                         }
                     }
                 },
-                MdElement::BlockQuote => serializer
+                MdElement::BlockQuote(None) => serializer
                     .blocks()?
                     .serialize_element()?
                     .serialize_block_quote(|blocks| {
@@ -389,15 +652,88 @@ impl<'book> Emitter<'book> {
                             Ok(())
                         })
                     }),
-                MdElement::InlineCode(s) => serializer.serialize_inlines(|inlines| {
-                    inlines.serialize_element()?.serialize_code((), s)
-                }),
+                MdElement::BlockQuote(Some(kind)) => {
+                    let style = serializer.preprocessor().preprocessor.ctx.admonition_style;
+                    let block = serializer.blocks()?.serialize_element()?;
+                    match style {
+                        // Like Pandoc's own `alerts` extension
+                        pandoc::AdmonitionStyle::Div => block.serialize_div(
+                            (None, &[CowStr::Borrowed(kind.class())], &[]),
+                            |blocks| {
+                                blocks.serialize_element()?.serialize_div(
+                                    (None, &[CowStr::Borrowed("title")], &[]),
+                                    |blocks| {
+                                        blocks.serialize_element()?.serialize_para(|inlines| {
+                                            inlines.serialize_element()?.serialize_str(kind.title())
+                                        })
+                                    },
+                                )?;
+                                blocks.serialize_nested(|serializer| {
+                                    self.serialize_children(node, serializer)
+                                })
+                            },
+                        ),
+                        pandoc::AdmonitionStyle::Blockquote => {
+                            block.serialize_block_quote(|blocks| {
+                                blocks.serialize_element()?.serialize_para(|inlines| {
+                                    inlines.serialize_element()?.serialize_strong(|inlines| {
+                                        inlines.serialize_element()?.serialize_str(kind.title())
+                                    })
+                                })?;
+                                blocks.serialize_nested(|serializer| {
+                                    self.serialize_children(node, serializer)
+                                })
+                            })
+                        }
+                    }
+                }
+                MdElement::InlineCode(s) => {
+                    let ctx = &mut serializer.preprocessor().preprocessor.ctx;
+                    let limit = ctx.code.break_inline_code_longer_than;
+                    if let (pandoc::OutputFormat::Latex { packages }, Some(limit)) =
+                        (&mut ctx.output, limit)
+                    {
+                        // LaTeX never breaks inline code, so long spans without spaces (e.g.
+                        // identifiers or URLs) overflow the margin unless breaks are allowed
+                        if s.chars().count() > limit {
+                            packages.need(latex::Package::SeqSplit);
+                            // `\seqsplit` drops spaces, so escape them
+                            let code = latex::escape(s).replace(' ', r"\ ");
+                            return serializer.serialize_inlines(|inlines| {
+                                inlines
+                                    .serialize_element()?
+                                    .serialize_raw_inline("latex", |raw| {
+                                        write!(raw, r"\texttt{{\seqsplit{{{code}}}}}")
+                                    })
+                            });
+                        }
+                    }
+                    serializer.serialize_inlines(|inlines| {
+                        inlines.serialize_element()?.serialize_code((), s)
+                    })
+                }
                 MdElement::CodeBlock(kind) => {
                     let ctx = &serializer.preprocessor().preprocessor.ctx;
 
-                    let code_block = code::CodeBlock::new(kind, ctx.html.map(|cfg| &cfg.code));
+                    // Adjacent code blocks are merged into the first of them
+                    let merge = ctx.code.merge_adjacent;
+                    if merge && (node.prev_sibling()).is_some_and(|prev| is_code_block(prev, kind))
+                    {
+                        return Ok(());
+                    }
+                    let merged = (node.next_siblings())
+                        .take_while(|&sibling| merge && is_code_block(sibling, kind));
 
-                    let lines = node.children().map(|node| {
+                    let code_block =
+                        code::CodeBlock::new(kind, ctx.html.map(|cfg| &cfg.code), ctx.code);
+                    let Some(code_block) =
+                        code_block.handle_unknown_language(ctx.code, ctx.highlight_languages)
+                    else {
+                        return Ok(());
+                    };
+
+                    let blocks = iter::once(node).chain(merged);
+                    let lines = blocks.flat_map(|node| node.children()).map(|node| {
                         match node.value() {
                             Node::Element(Element::Markdown(MdElement::Text(text))) => text,
                             event => panic!("Code blocks should contain only literal text, but encountered {event:?}"),
@@ -406,9 +742,109 @@ impl<'book> Emitter<'book> {
                     let lines = code_block.lines(lines, ctx.code);
 
                     let mut language = code_block.language();
+                    let latex = matches!(ctx.output, pandoc::OutputFormat::Latex { .. });
+                    if let (true, Some("dot" | "graphviz")) = (ctx.code.graphviz, language) {
+                        // LaTeX can't include SVGs directly, so have Graphviz produce a PDF instead
+                        let format = if latex { "pdf" } else { "svg" };
+                        let source = lines.join("\n");
+                        let chapter = serializer.preprocessor();
+                        match chapter.render_graphviz(&source, format) {
+                            Ok(path) => {
+                                return serializer.serialize_inlines(|inlines| {
+                                    inlines.serialize_element()?.serialize_image(
+                                        (),
+                                        |_alt| Ok(()),
+                                        &path.to_string_lossy(),
+                                        "",
+                                    )
+                                })
+                            }
+                            Err(err) => log::warn!(
+                                "Unable to render Graphviz graph in chapter '{}' ({err:#}); \
+                                rendering it as a code block instead",
+                                chapter.chapter.name
+                            ),
+                        }
+                    }
+                    match (code_block.label(), code_block.filename()) {
+                        (Some(label), filename) if latex => {
+                            let label = serializer.preprocessor().listing_label(label);
+                            Self::serialize_listing_caption(&label, filename, serializer)?;
+                        }
+                        (_, Some(filename)) => Self::serialize_code_filename(filename, serializer)?,
+                        (_, None) => {}
+                    }
 
-                    if let pandoc::OutputFormat::Latex { .. } =
-                        serializer.preprocessor().preprocessor.ctx.output
+                    let ctx = &mut serializer.preprocessor().preprocessor.ctx;
+                    if let (true, Some("diff"), pandoc::OutputFormat::Latex { packages }) =
+                        (ctx.code.style_diffs, language, &mut ctx.output)
+                    {
+                        // Switch colors at the start of each line using an escape character that
+                        // doesn't otherwise appear in the code
+                        const ESCAPE_CHARS: &[char] = &['`', '|', '!', '~', '^'];
+                        let escape = (ESCAPE_CHARS.iter().copied())
+                            .find(|&c| !lines.iter().any(|line| line.contains(c)));
+                        if let Some(escape) = escape {
+                            packages.need(latex::Package::Listings);
+                            packages.need(latex::Package::XColor);
+                            return serializer
+                                .blocks()?
+                                .serialize_element()?
+                                .serialize_raw_block("latex", |raw| {
+                                    raw.serialize_code(
+                                        r"\begin{lstlisting}[basicstyle=\ttfamily, ",
+                                    )?;
+                                    raw.serialize_code(&format!(
+                                        "columns=fullflexible, escapechar={escape}]"
+                                    ))?;
+                                    for line in lines {
+                                        let color = match line.chars().next() {
+                                            Some('+') => "green!50!black",
+                                            Some('-') => "red",
+                                            Some('@') => "blue",
+                                            _ => "black",
+                                        };
+                                        raw.serialize_code(&format!(
+                                            "\n{escape}\\color{{{color}}}{escape}{line}"
+                                        ))?;
+                                    }
+                                    raw.serialize_code("\n\\end{lstlisting}")
+                                });
+                        }
+                    }
+
+                    let ctx = &mut serializer.preprocessor().preprocessor.ctx;
+                    if let (
+                        pandoc::CodeBlockBackend::Minted,
+                        pandoc::OutputFormat::Latex { packages },
+                    ) = (ctx.code_block_backend, &mut ctx.output)
+                    {
+                        packages.need(latex::Package::Minted);
+                        // Pygments calls plain text "text", and fails on languages it has no
+                        // lexer for
+                        let known = |language: &&str| {
+                            (ctx.pygments_lexers)
+                                .map_or(true, |lexers| lexers.contains(&language.to_lowercase()))
+                        };
+                        let language = language.filter(known).unwrap_or("text");
+                        return serializer
+                            .blocks()?
+                            .serialize_element()?
+                            .serialize_raw_block("latex", |raw| {
+                                raw.serialize_code(&format!(
+                                    "\\begin{{minted}}[breaklines]{{{language}}}\n"
+                                ))?;
+                                for line in lines {
+                                    raw.serialize_code(&line)?;
+                                    raw.serialize_code("\n")?;
+                                }
+                                raw.serialize_code(r"\end{minted}")
+                            });
+                    }
+
+                    let ctx = &serializer.preprocessor().preprocessor.ctx;
+                    if ctx.code_block_backend == pandoc::CodeBlockBackend::Fvextra
+                        && matches!(ctx.output, pandoc::OutputFormat::Latex { .. })
                     {
                         const CODE_BLOCK_LINE_LENGTH_LIMIT: usize = 1000;
 
@@ -491,15 +927,16 @@ impl<'book> Emitter<'book> {
                     title,
                     id,
                 } => serializer.serialize_inlines(|inlines| {
-                    match inlines
-                        .serializer
-                        .preprocessor
-                        .resolve_image_url(dest_url.as_ref().into(), *link_type)
-                    {
-                        Err(UnresolvableRemoteImage) => inlines
+                    let preprocessor = &mut inlines.serializer.preprocessor;
+                    let attrs = (preprocessor.preprocessor.ctx.max_image_width.as_deref())
+                        .map(|width| (CowStr::Borrowed("width"), Some(width.to_owned().into())))
+                        .into_iter()
+                        .collect::<Vec<_>>();
+                    match preprocessor.resolve_image_url(dest_url.as_ref().into(), *link_type) {
+                        Err(UnresolvableImage) => inlines
                             .serialize_nested(|inlines| self.serialize_children(node, inlines)),
                         Ok(dest_url) => inlines.serialize_element()?.serialize_image(
-                            (Some(id.as_ref()), &[], &[]),
+                            (Some(id.as_ref()), &[], &attrs),
                             |alt| alt.serialize_nested(|alt| self.serialize_children(node, alt)),
                             &dest_url,
                             title,
@@ -508,7 +945,33 @@ impl<'book> Emitter<'book> {
                 }),
             },
             Node::Element(Element::Html(element)) => {
+                let ctx = &serializer.preprocessor().preprocessor.ctx;
+                if !matches!(ctx.output, pandoc::OutputFormat::HtmlLike)
+                    && is_interactive(element, ctx.strip_interactive)
+                {
+                    return Ok(());
+                }
+                for attr in [
+                    html::name!("href"),
+                    html::name!("src"),
+                    html::name!("poster"),
+                ] {
+                    if let Some(url) = element.attrs.rest.get(&attr) {
+                        serializer.preprocessor().copy_html_asset(url);
+                    }
+                }
+                // Render elements that only make sense inside particular containers like any other
+                // when they're found elsewhere
+                let orphaned = orphaned_container(node, &element.name.local);
+                if let Some(container) = &orphaned {
+                    log::warn!(
+                        "Found <{}> outside of <{container}> in chapter '{}'",
+                        element.name.local,
+                        serializer.preprocessor().chapter.name,
+                    );
+                }
                 match element.name.local {
+                    _ if orphaned.is_some() => {}
                     local_name!("thead")
                     | local_name!("th")
                     | local_name!("tr")
@@ -519,10 +982,24 @@ impl<'book> Emitter<'book> {
                         })
                     }
                     local_name!("hr") => {
-                        return serializer
-                            .blocks()?
-                            .serialize_element()?
-                            .serialize_horizontal_rule()
+                        let ctx = &serializer.preprocessor().preprocessor.ctx;
+                        let (horizontal_rule, latex) = (
+                            ctx.horizontal_rule,
+                            matches!(ctx.output, pandoc::OutputFormat::Latex { .. }),
+                        );
+                        return match horizontal_rule {
+                            HorizontalRule::Ignore => Ok(()),
+                            HorizontalRule::PageBreak if latex => serializer
+                                .blocks()?
+                                .serialize_element()?
+                                .serialize_raw_block("latex", |raw| {
+                                    raw.serialize_code(r"\clearpage")
+                                }),
+                            HorizontalRule::Rule | HorizontalRule::PageBreak => serializer
+                                .blocks()?
+                                .serialize_element()?
+                                .serialize_horizontal_rule(),
+                        };
                     }
                     local_name!("a") => {
                         let [href, title] = [html::name!("href"), html::name!("title")]
@@ -551,6 +1028,37 @@ impl<'book> Emitter<'book> {
                             }
                         });
                     }
+                    local_name!("span")
+                        if serializer.preprocessor().preprocessor.ctx.sidenotes
+                            && (element.attrs.classes.split_whitespace())
+                                .any(|class| class == "sidenote") =>
+                    {
+                        let ctx = &mut serializer.preprocessor().preprocessor.ctx;
+                        if let pandoc::OutputFormat::Latex { packages } = &mut ctx.output {
+                            packages.need(latex::Package::Sidenotes);
+                            return serializer.serialize_inlines(|inlines| {
+                                (inlines.serialize_element()?)
+                                    .serialize_raw_inline("latex", |raw| {
+                                        raw.write_all(br"\sidenote{")
+                                    })?;
+                                inlines.serialize_nested(|serializer| {
+                                    self.serialize_children(node, serializer)
+                                })?;
+                                (inlines.serialize_element()?)
+                                    .serialize_raw_inline("latex", |raw| raw.write_all(b"}"))
+                            });
+                        }
+                        // Other formats have no margins to speak of, so use a regular footnote
+                        return serializer.serialize_inlines(|inlines| {
+                            inlines.serialize_element()?.serialize_note(|blocks| {
+                                blocks.serialize_element()?.serialize_para(|inlines| {
+                                    inlines.serialize_nested(|serializer| {
+                                        self.serialize_children(node, serializer)
+                                    })
+                                })
+                            })
+                        });
+                    }
                     local_name!("span") => {
                         return serializer.serialize_inlines(|inlines| {
                             inlines
@@ -562,9 +1070,31 @@ impl<'book> Emitter<'book> {
                                 })
                         })
                     }
+                    local_name!("div") if serializer.is_blocks() => {
+                        let ctx = &serializer.preprocessor().preprocessor.ctx;
+                        let tabbed = (ctx.flatten_tabs)
+                            .filter(|_| !matches!(ctx.output, pandoc::OutputFormat::HtmlLike))
+                            .is_some_and(|class| {
+                                element.attrs.classes.split_whitespace().any(|c| c == class)
+                            });
+                        if let Some(panels) = tabbed.then(|| tab_panels(node)).flatten() {
+                            return self.serialize_tab_panels(node, panels, serializer);
+                        }
+                        let attrs = admonition_attrs(&element.attrs, &ctx.output);
+                        return serializer.blocks()?.serialize_element()?.serialize_div(
+                            &*attrs,
+                            |blocks| {
+                                blocks.serialize_nested(|serializer| {
+                                    self.serialize_children(node, serializer)
+                                })
+                            },
+                        );
+                    }
                     local_name!("div") => {
+                        let ctx = &serializer.preprocessor().preprocessor.ctx;
+                        let attrs = admonition_attrs(&element.attrs, &ctx.output);
                         return serializer.blocks()?.serialize_element()?.serialize_div(
-                            &element.attrs,
+                            &*attrs,
                             |blocks| {
                                 blocks.serialize_nested(|serializer| {
                                     self.serialize_children(node, serializer)
@@ -578,11 +1108,18 @@ impl<'book> Emitter<'book> {
                             [html::name!("src"), html::name!("alt"), html::name!("title")]
                                 .map(|attr| attrs.rest.swap_remove(&attr));
                         let Some(src) = src else { return Ok(()) };
+                        let [width, height] = [html::name!("width"), html::name!("height")];
+                        if !(attrs.rest.contains_key(&width) || attrs.rest.contains_key(&height)) {
+                            let ctx = &serializer.preprocessor().preprocessor.ctx;
+                            if let Some(max_width) = ctx.max_image_width.as_deref() {
+                                attrs.rest.insert(width, StrTendril::from_slice(max_width));
+                            }
+                        }
                         return match serializer
                             .preprocessor()
                             .resolve_image_url(src.as_ref().into(), LinkType::Inline)
                         {
-                            Err(UnresolvableRemoteImage) => match alt {
+                            Err(UnresolvableImage) => match alt {
                                 Some(alt) => serializer.serialize_inlines(|serializer| {
                                     serializer.serialize_element()?.serialize_str(&alt)
                                 }),
@@ -603,6 +1140,21 @@ impl<'book> Emitter<'book> {
                             }),
                         };
                     }
+                    local_name!("figure") => {
+                        let subfigures = (node.children())
+                            .filter(|&child| is_html_element(child, local_name!("figure")))
+                            .count();
+                        let is_blocks = serializer.is_blocks();
+                        let ctx = &mut serializer.preprocessor().preprocessor.ctx;
+                        if let (true, pandoc::OutputFormat::Latex { packages }) =
+                            (ctx.subfigures, &mut ctx.output)
+                        {
+                            if subfigures > 1 && is_blocks {
+                                packages.need(latex::Package::Subcaption);
+                                return self.serialize_subfigures(node, serializer);
+                            }
+                        }
+                    }
                     local_name!("i") => {
                         let Attributes { id, classes, rest } = &element.attrs;
                         if id.is_none() && rest.is_empty() {
@@ -623,6 +1175,64 @@ impl<'book> Emitter<'book> {
                             }
                         }
                     }
+                    local_name!("abbr") => {
+                        let ctx = &serializer.preprocessor().preprocessor.ctx;
+                        let short = text_content(node);
+                        let short = short.trim();
+                        if matches!(ctx.output, pandoc::OutputFormat::Latex { .. })
+                            && ctx.acronyms.contains_key(short)
+                        {
+                            return serializer.serialize_inlines(|inlines| {
+                                inlines
+                                    .serialize_element()?
+                                    .serialize_raw_inline("latex", |raw| {
+                                        write!(raw, r"\gls{{{short}}}")
+                                    })
+                            });
+                        }
+                    }
+                    local_name!("kbd") => {
+                        let ctx = &mut serializer.preprocessor().preprocessor.ctx;
+                        if let (true, pandoc::OutputFormat::Latex { packages }) =
+                            (ctx.keyboard_shortcuts, &mut ctx.output)
+                        {
+                            packages.need(latex::Package::MenuKeys);
+                            let keys = shortcut_keys(node)
+                                .map(|key| latex::escape(text_content(key).trim()))
+                                .collect::<Vec<_>>()
+                                .join(" + ");
+                            return serializer.serialize_inlines(|inlines| {
+                                inlines
+                                    .serialize_element()?
+                                    .serialize_raw_inline("latex", |raw| {
+                                        write!(raw, r"\keys{{{keys}}}")
+                                    })
+                            });
+                        }
+                    }
+                    local_name!("table") if serializer.is_blocks() => {
+                        if let Some(table) = HtmlTable::new(node) {
+                            return self.serialize_html_table(&element.attrs, table, serializer);
+                        }
+                    }
+                    local_name!("svg") => {
+                        let ctx = &mut serializer.preprocessor().preprocessor.ctx;
+                        if let (SvgInLatex::Vector, pandoc::OutputFormat::Latex { packages }) =
+                            (ctx.svg_in_latex, &mut ctx.output)
+                        {
+                            packages.need(latex::Package::Svg);
+                            let mut svg = Vec::new();
+                            Self::write_svg(node, &mut svg)?;
+                            let path = serializer.preprocessor().write_svg(&svg)?;
+                            return serializer.serialize_inlines(|inlines| {
+                                inlines
+                                    .serialize_element()?
+                                    .serialize_raw_inline("latex", |raw| {
+                                        write!(raw, r"\includesvg{{{}}}", path.display())
+                                    })
+                            });
+                        }
+                    }
                     _ => {}
                 }
                 serializer.serialize_raw_html(|serializer| {
@@ -645,7 +1255,7 @@ impl<'book> Emitter<'book> {
                 if node.has_children() || id.is_some() {
                     let attrs = (id, &[], &[]);
                     if serializer.is_blocks() {
-                        if element.name.is_display_block() {
+                        if is_display_block(element, &serializer.preprocessor().preprocessor.ctx) {
                             serializer.blocks()?.serialize_element()?.serialize_div(
                                 attrs,
                                 |serializer| {
@@ -675,10 +1285,361 @@ impl<'book> Emitter<'book> {
         }
     }
 
+    /// Serializes a numbered LaTeX caption for a code block labelled as a listing, captioned by
+    /// the name of the file it belongs to if any.
+    fn serialize_listing_caption(
+        label: &str,
+        filename: Option<&str>,
+        serializer: &mut pandoc::native::SerializeNested<'_, '_, 'book, '_, impl io::Write>,
+    ) -> anyhow::Result<()> {
+        if let pandoc::OutputFormat::Latex { packages } =
+            &mut serializer.preprocessor().preprocessor.ctx.output
+        {
+            packages.need(latex::Package::Caption);
+            packages.need(latex::Package::NewFloat);
+        }
+        let caption = filename.map_or_else(String::new, |filename| {
+            format!(r"\texttt{{{}}}", latex::escape(filename))
+        });
+        serializer
+            .blocks()?
+            .serialize_element()?
+            .serialize_raw_block("latex", |raw| {
+                raw.serialize_code(&format!(
+                    r"\captionof{{listing}}{{{caption}}}\label{{{label}}}\nopagebreak"
+                ))
+            })
+    }
+
+    /// Serializes a label naming the file a code block belongs to, to be placed above the block.
+    fn serialize_code_filename(
+        filename: &str,
+        serializer: &mut pandoc::native::SerializeNested<'_, '_, 'book, '_, impl io::Write>,
+    ) -> anyhow::Result<()> {
+        let latex = matches!(
+            serializer.preprocessor().preprocessor.ctx.output,
+            pandoc::OutputFormat::Latex { .. }
+        );
+        let block = serializer.blocks()?.serialize_element()?;
+        if latex {
+            block.serialize_raw_block("latex", |raw| {
+                raw.serialize_code(&format!(
+                    r"\noindent\textbf{{\texttt{{{}}}}}\par\nopagebreak",
+                    latex::escape(filename)
+                ))
+            })
+        } else {
+            block.serialize_div((None, &[CowStr::Borrowed("filename")], &[]), |blocks| {
+                blocks.serialize_nested(|serializer| {
+                    serializer.serialize_inlines(|inlines| {
+                        inlines.serialize_element()?.serialize_code((), filename)
+                    })
+                })
+            })
+        }
+    }
+
+    /// Serializes the panels of a tabbed content container as unnumbered subsections of the
+    /// section containing it, each titled by its tab's label.
+    fn serialize_tab_panels(
+        &self,
+        container: NodeRef<'_, Node>,
+        panels: Vec<(String, NodeRef<'_, Node>)>,
+        serializer: &mut pandoc::native::SerializeNested<'_, '_, 'book, '_, impl io::Write>,
+    ) -> anyhow::Result<()> {
+        let level = section_level(container).map_or(2, |level| (level + 1).min(6));
+        let classes = [CowStr::Borrowed("unnumbered"), CowStr::Borrowed("unlisted")];
+        for (label, panel) in panels {
+            serializer.blocks()?.serialize_element()?.serialize_header(
+                level,
+                (None, &classes, &[]),
+                |inlines| inlines.serialize_element()?.serialize_str(&label),
+            )?;
+            self.serialize_children(panel, serializer)?;
+        }
+        Ok(())
+    }
+
+    /// Serializes the contents of a list item, wrapping its text in paragraphs if lists should be
+    /// loose.
+    fn serialize_list_item(
+        &self,
+        item: NodeRef<'_, Node>,
+        serializer: &mut pandoc::native::SerializeNested<'_, '_, 'book, '_, impl io::Write>,
+    ) -> anyhow::Result<()> {
+        if serializer.preprocessor().preprocessor.ctx.list_spacing != ListSpacing::Loose {
+            return self.serialize_children(item, serializer);
+        }
+        let mut inlines = vec![];
+        let mut children = item.children().peekable();
+        while let Some(node) = children.next() {
+            if !is_inline(node) {
+                self.serialize_node(node, serializer)?;
+                continue;
+            }
+            inlines.push(node);
+            if children.peek().is_some_and(|&next| is_inline(next)) {
+                continue;
+            }
+            serializer
+                .blocks()?
+                .serialize_element()?
+                .serialize_para(|serializer| {
+                    serializer.serialize_nested(|serializer| {
+                        for node in inlines.drain(..) {
+                            self.serialize_node(node, serializer)?;
+                        }
+                        Ok(())
+                    })
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Serializes a simple HTML `<table>` as a native table, like a Markdown table.
+    fn serialize_html_table(
+        &self,
+        attrs: &Attributes,
+        table: HtmlTable<'_, '_>,
+        serializer: &mut pandoc::native::SerializeNested<'_, '_, 'book, '_, impl io::Write>,
+    ) -> anyhow::Result<()> {
+        let no_attrs = Attributes::default();
+        let head_attrs = table.head.and_then(html_attributes).unwrap_or(&no_attrs);
+        let body_attrs = html_attributes(table.body).unwrap_or(&no_attrs);
+        serializer.blocks()?.serialize_element()?.serialize_table(
+            attrs,
+            (table.alignment.iter().copied().map(Into::into)).zip(iter::repeat_with(|| None)),
+            (head_attrs, |rows| match table.head {
+                Some(head) => self.serialize_html_table_rows(head, &table.alignment, rows),
+                None => Ok(()),
+            }),
+            (body_attrs, |rows| {
+                self.serialize_html_table_rows(table.body, &table.alignment, rows)
+            }),
+        )
+    }
+
+    fn serialize_html_table_rows(
+        &self,
+        section: NodeRef<'_, Node>,
+        alignment: &[Alignment],
+        rows: &mut pandoc::native::SerializeRows<'_, 'book, '_, impl io::Write>,
+    ) -> anyhow::Result<()> {
+        for row in significant_children(section) {
+            let Node::Element(Element::Html(element)) = row.value() else {
+                anyhow::bail!("expected table row, got {:?}", row.value());
+            };
+            rows.serialize_element()?
+                .serialize_row(&element.attrs, |cells| {
+                    for (cell, &column) in significant_children(row).zip(alignment) {
+                        let Node::Element(Element::Html(element)) = cell.value() else {
+                            anyhow::bail!("expected table cell, got {:?}", cell.value());
+                        };
+                        // Alignment is represented by the column, or by the cell if they differ
+                        let mut attrs = element.attrs.clone();
+                        attrs.rest.swap_remove(&html::name!("align"));
+                        let align = match html_alignment(&element.attrs) {
+                            align if align == column => Alignment::None,
+                            align => align,
+                        };
+                        cells.serialize_element()?.serialize_cell(
+                            &attrs,
+                            align.into(),
+                            |blocks| {
+                                blocks.serialize_nested(|serializer| {
+                                    self.serialize_children(cell, serializer)
+                                })
+                            },
+                        )?;
+                    }
+                    Ok(())
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Writes the `<svg>` element `node` and its descendants as a standalone SVG document.
+    ///
+    /// HTML serialization doesn't produce well-formed XML (it writes entities like `&nbsp;` and
+    /// leaves the contents of `<style>` and `<script>` unescaped), so the SVG is written as XML,
+    /// declaring the namespaces of its elements and prefixed attributes.
+    fn write_svg(node: NodeRef<'_, Node>, mut writer: impl io::Write) -> io::Result<()> {
+        fn escape(text: &str, attribute: bool) -> Cow<'_, str> {
+            let special = |c| matches!(c, '&' | '<' | '>') || (attribute && c == '"');
+            if !text.contains(special) {
+                return text.into();
+            }
+            let mut escaped = String::with_capacity(text.len());
+            for c in text.chars() {
+                match c {
+                    '&' => escaped.push_str("&amp;"),
+                    '<' => escaped.push_str("&lt;"),
+                    '>' => escaped.push_str("&gt;"),
+                    '"' if attribute => escaped.push_str("&quot;"),
+                    c => escaped.push(c),
+                }
+            }
+            escaped.into()
+        }
+
+        fn write_node(
+            node: NodeRef<'_, Node>,
+            writer: &mut impl io::Write,
+            parent_ns: Option<&html5ever::Namespace>,
+        ) -> io::Result<()> {
+            let element = match node.value() {
+                Node::HtmlText(text) => return write!(writer, "{}", escape(text, false)),
+                // Comments may contain `--`, which XML doesn't allow, and aren't needed to render
+                Node::HtmlComment(_) => return Ok(()),
+                Node::Element(Element::Html(element)) => element,
+                Node::Document | Node::Element(Element::Markdown(_)) => return Ok(()),
+            };
+            let name = &element.name.local;
+            write!(writer, "<{name}")?;
+            let mut declared = (element.attrs.iter())
+                .filter_map(|(attr, _)| match (&attr.prefix, &*attr.local) {
+                    (None, "xmlns") => Some(String::new()),
+                    (Some(prefix), local) if &**prefix == "xmlns" => Some(local.to_owned()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            // Inline SVGs may omit the namespace declarations standalone files require
+            if parent_ns != Some(&element.name.ns) && !declared.contains(&String::new()) {
+                write!(writer, r#" xmlns="{}""#, escape(&element.name.ns, true))?;
+            }
+            for (attr, value) in element.attrs.iter() {
+                match &attr.prefix {
+                    Some(prefix) => {
+                        // The `xml` prefix is bound implicitly
+                        if &**prefix != "xml"
+                            && &**prefix != "xmlns"
+                            && !declared.contains(&prefix.to_string())
+                        {
+                            write!(writer, r#" xmlns:{prefix}="{}""#, escape(&attr.ns, true))?;
+                            declared.push(prefix.to_string());
+                        }
+                        write!(
+                            writer,
+                            r#" {prefix}:{}="{}""#,
+                            attr.local,
+                            escape(value, true)
+                        )?;
+                    }
+                    None => write!(writer, r#" {}="{}""#, attr.local, escape(value, true))?,
+                }
+            }
+            if node.children().next().is_none() {
+                return write!(writer, "/>");
+            }
+            write!(writer, ">")?;
+            for child in node.children() {
+                write_node(child, writer, Some(&element.name.ns))?;
+            }
+            write!(writer, "</{name}>")
+        }
+
+        writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        write_node(node, &mut writer, None)
+    }
+
+    /// Serializes a `<figure>` containing nested `<figure>`s as a LaTeX figure with a
+    /// `\subcaptionbox` per subfigure.
+    fn serialize_subfigures(
+        &self,
+        figure: NodeRef<'_, Node>,
+        serializer: &mut pandoc::native::SerializeNested<'_, '_, 'book, '_, impl io::Write>,
+    ) -> anyhow::Result<()> {
+        fn raw_latex<W: io::Write>(
+            serializer: &mut pandoc::native::SerializeNested<'_, '_, '_, '_, W>,
+            latex: &str,
+        ) -> anyhow::Result<()> {
+            serializer.serialize_inlines(|inlines| {
+                inlines
+                    .serialize_element()?
+                    .serialize_raw_inline("latex", |raw| raw.write_all(latex.as_bytes()))
+            })
+        }
+
+        fn caption<'a, 'b>(figure: NodeRef<'a, Node<'b>>) -> Option<NodeRef<'a, Node<'b>>> {
+            (figure.children()).find(|&child| is_html_element(child, local_name!("figcaption")))
+        }
+
+        let blocks = serializer.blocks()?;
+        blocks
+            .serialize_element()?
+            .serialize_raw_block("latex", |raw| {
+                raw.serialize_code("\\begin{figure}\n\\centering")
+            })?;
+        blocks.serialize_element()?.serialize_para(|inlines| {
+            inlines.serialize_nested(|serializer| {
+                let subfigures = (figure.children())
+                    .filter(|&child| is_html_element(child, local_name!("figure")));
+                for (idx, subfigure) in subfigures.enumerate() {
+                    if idx > 0 {
+                        raw_latex(serializer, r"\hfill")?;
+                    }
+                    raw_latex(serializer, r"\subcaptionbox{")?;
+                    if let Some(caption) = caption(subfigure) {
+                        self.serialize_children(caption, serializer)?;
+                    }
+                    raw_latex(serializer, "}{")?;
+                    for node in subfigure.children() {
+                        match node.value() {
+                            Node::HtmlText(text) if text.trim().is_empty() => {}
+                            _ if is_html_element(node, local_name!("figcaption")) => {}
+                            _ => self.serialize_node(node, serializer)?,
+                        }
+                    }
+                    raw_latex(serializer, "}")?;
+                }
+                Ok(())
+            })
+        })?;
+        // Keep any other content of the figure, e.g. a credit line, below the subfigures
+        let others = figure.children().filter(|&child| match child.value() {
+            Node::HtmlText(text) => !text.trim().is_empty(),
+            _ => {
+                !is_html_element(child, local_name!("figure"))
+                    && !is_html_element(child, local_name!("figcaption"))
+            }
+        });
+        blocks.serialize_nested(|serializer| {
+            for node in others {
+                self.serialize_node(node, serializer)?;
+            }
+            Ok(())
+        })?;
+        if let Some(caption) = caption(figure) {
+            blocks.serialize_element()?.serialize_para(|inlines| {
+                inlines.serialize_nested(|serializer| {
+                    raw_latex(serializer, r"\caption{")?;
+                    self.serialize_children(caption, serializer)?;
+                    raw_latex(serializer, "}")
+                })
+            })?;
+        }
+        blocks
+            .serialize_element()?
+            .serialize_raw_block("latex", |raw| raw.serialize_code(r"\end{figure}"))
+    }
+
     pub fn emit(
         self,
         serializer: &mut pandoc::native::SerializeBlocks<'_, 'book, '_, impl io::Write>,
     ) -> anyhow::Result<()> {
+        let preprocessor = &serializer.serializer.preprocessor;
+        let ctx = &preprocessor.preprocessor.ctx;
+        if let (true, pandoc::OutputFormat::HtmlLike, Some(path)) = (
+            ctx.annotate_chapters,
+            &ctx.output,
+            &preprocessor.chapter().source_path,
+        ) {
+            let comment = format!("<!-- chapter: {} -->", path.display());
+            serializer
+                .serialize_element()?
+                .serialize_raw_block("html", |raw| raw.serialize_code(&comment))?;
+        }
+
         let preprocessor = &mut serializer.serializer.preprocessor;
         let chapter = preprocessor.chapter();
         if chapter.number.is_none() && preprocessor.part_num() > 0 {
@@ -692,8 +1653,457 @@ impl<'book> Emitter<'book> {
         }
 
         let root = self.tree.tree.root().first_child().unwrap();
-        serializer.serialize_nested(|serializer| self.serialize_children(root, serializer))
+
+        // Links to chapters point at their first heading, so give those without any an anchor
+        if serializer.serializer.preprocessor.is_html_only() {
+            let anchor = match serializer.serializer.preprocessor.id_prefix() {
+                Some(prefix) => format!("{prefix}{CHAPTER_START_ANCHOR}"),
+                None => CHAPTER_START_ANCHOR.to_owned(),
+            };
+            serializer
+                .serialize_element()?
+                .serialize_div((Some(anchor.as_str()), &[], &[]), |_| Ok(()))?;
+        }
+
+        let ctx = &serializer.serializer.preprocessor.preprocessor.ctx;
+        let fold_level =
+            (ctx.fold_level).filter(|_| matches!(ctx.output, pandoc::OutputFormat::HtmlLike));
+        serializer.serialize_nested(|serializer| match fold_level {
+            Some(fold_level) => self.serialize_folded_sections(root, fold_level, serializer),
+            None => self.serialize_children(root, serializer),
+        })
     }
+
+    /// Serializes the top-level blocks of a chapter, wrapping sections whose headings are at or
+    /// below `fold_level` in `<details>` elements so they render collapsed.
+    fn serialize_folded_sections(
+        &self,
+        root: NodeRef<'_, Node>,
+        fold_level: usize,
+        serializer: &mut pandoc::native::SerializeNested<'_, '_, 'book, '_, impl io::Write>,
+    ) -> anyhow::Result<()> {
+        // Levels of the sections currently open
+        let mut open_sections: Vec<usize> = vec![];
+        for node in root.children() {
+            if let Node::Element(Element::Markdown(MdElement::Heading { level, .. })) = node.value()
+            {
+                let level = *level as usize;
+                while open_sections.last().is_some_and(|&open| open >= level) {
+                    open_sections.pop();
+                    Self::serialize_raw_html_block("</details>", serializer)?;
+                }
+                if level >= fold_level {
+                    open_sections.push(level);
+                    Self::serialize_raw_html_block("<details>", serializer)?;
+                    Self::serialize_raw_html_block("<summary>", serializer)?;
+                    self.serialize_node(node, serializer)?;
+                    Self::serialize_raw_html_block("</summary>", serializer)?;
+                    continue;
+                }
+            }
+            self.serialize_node(node, serializer)?;
+        }
+        for _ in open_sections {
+            Self::serialize_raw_html_block("</details>", serializer)?;
+        }
+        Ok(())
+    }
+
+    /// Serializes a raw HTML block, e.g. a lone opening or closing tag.
+    fn serialize_raw_html_block(
+        html: &str,
+        serializer: &mut pandoc::native::SerializeNested<'_, '_, 'book, '_, impl io::Write>,
+    ) -> anyhow::Result<()> {
+        (serializer.blocks()?.serialize_element()?)
+            .serialize_raw_block("html", |raw| raw.serialize_code(html))
+    }
+
+    fn serialize_paragraph(
+        &self,
+        paragraph: NodeRef<'_, Node>,
+        serializer: &mut pandoc::native::SerializeNested<'_, '_, 'book, '_, impl io::Write>,
+    ) -> anyhow::Result<()> {
+        serializer
+            .blocks()?
+            .serialize_element()?
+            .serialize_para(|serializer| {
+                serializer.serialize_nested(|serializer| {
+                    for node in paragraph.children() {
+                        self.serialize_node(node, serializer)?;
+                    }
+                    Ok(())
+                })
+            })
+    }
+
+    /// Serializes a raw LaTeX block, e.g. the start or end of an environment.
+    fn serialize_raw_latex_block(
+        latex: &str,
+        serializer: &mut pandoc::native::SerializeNested<'_, '_, 'book, '_, impl io::Write>,
+    ) -> anyhow::Result<()> {
+        (serializer.blocks()?.serialize_element()?)
+            .serialize_raw_block("latex", |raw| raw.serialize_code(latex))
+    }
+}
+
+/// Marks `term` as an entry in the index: `{{#index term}}`.
+static INDEX_MARKER: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{\{#index\s+([^}]+)\}\}").unwrap());
+
+/// An HTML `<table>` simple enough to be represented by a native Pandoc table: an optional
+/// `<thead>` and a `<tbody>` whose rows all contain the same number of cells, none of which span
+/// multiple rows or columns.
+struct HtmlTable<'a, 'book> {
+    head: Option<NodeRef<'a, Node<'book>>>,
+    body: NodeRef<'a, Node<'book>>,
+    /// Column alignments, taken from the `align` attributes of the cells in the first row.
+    alignment: Vec<Alignment>,
+}
+
+impl<'a, 'book> HtmlTable<'a, 'book> {
+    fn new(table: NodeRef<'a, Node<'book>>) -> Option<Self> {
+        let (head, body) = match significant_children(table).collect::<Vec<_>>()[..] {
+            [head, body] if is_html_element(head, local_name!("thead")) => (Some(head), body),
+            [body] => (None, body),
+            _ => return None,
+        };
+        if !is_html_element(body, local_name!("tbody")) {
+            return None;
+        }
+
+        let mut columns = None;
+        for row in (head.into_iter().chain([body])).flat_map(significant_children) {
+            if !is_html_element(row, local_name!("tr")) {
+                return None;
+            }
+            let mut cells = 0;
+            for cell in significant_children(row) {
+                match cell.value() {
+                    Node::Element(Element::Html(element))
+                        if matches!(element.name.local, local_name!("th") | local_name!("td"))
+                            && !element.attrs.rest.contains_key(&html::name!("rowspan"))
+                            && !element.attrs.rest.contains_key(&html::name!("colspan")) => {}
+                    _ => return None,
+                }
+                cells += 1;
+            }
+            if *columns.get_or_insert(cells) != cells {
+                return None;
+            }
+        }
+
+        let first_row = significant_children(head.unwrap_or(body)).next();
+        let alignment = (first_row.into_iter().flat_map(significant_children))
+            .map(|cell| html_attributes(cell).map_or(Alignment::None, html_alignment))
+            .collect();
+        Some(Self {
+            head,
+            body,
+            alignment,
+        })
+    }
+}
+
+/// The alignment given by an HTML element's `align` attribute.
+fn html_alignment(attrs: &Attributes) -> Alignment {
+    match attrs.rest.get(&html::name!("align")).map(|s| s.as_ref()) {
+        Some("left") => Alignment::Left,
+        Some("center") => Alignment::Center,
+        Some("right") => Alignment::Right,
+        _ => Alignment::None,
+    }
+}
+
+fn html_attributes<'a>(node: NodeRef<'a, Node>) -> Option<&'a Attributes> {
+    match node.value() {
+        Node::Element(Element::Html(element)) => Some(&element.attrs),
+        _ => None,
+    }
+}
+
+/// The children of `node`, excluding comments and whitespace.
+fn significant_children<'a, 'book>(
+    node: NodeRef<'a, Node<'book>>,
+) -> impl Iterator<Item = NodeRef<'a, Node<'book>>> {
+    node.children().filter(|child| match child.value() {
+        Node::HtmlComment(_) => false,
+        Node::HtmlText(text) => !text.trim().is_empty(),
+        Node::Document | Node::Element(_) => true,
+    })
+}
+
+/// The concatenated text of `node` and its descendants.
+fn text_content(node: NodeRef<'_, Node>) -> String {
+    (node.descendants())
+        .filter_map(|node| match node.value() {
+            Node::HtmlText(text) => Some(text.as_ref()),
+            Node::Element(Element::Markdown(MdElement::Text(text))) => Some(text.as_ref()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The keys of the keyboard shortcut starting at `key`, e.g. `<kbd>Ctrl</kbd>+<kbd>C</kbd>`.
+fn shortcut_keys<'a, 'book>(
+    key: NodeRef<'a, Node<'book>>,
+) -> impl Iterator<Item = NodeRef<'a, Node<'book>>> {
+    iter::successors(Some(key), |key| {
+        (key.next_sibling())
+            .filter(|&separator| is_key_separator(separator))
+            .and_then(|separator| separator.next_sibling())
+    })
+}
+
+/// Whether `node` is a `+` joining two `<kbd>` elements.
+fn is_key_separator(node: NodeRef<'_, Node>) -> bool {
+    let text = match node.value() {
+        Node::HtmlText(text) => text.as_ref(),
+        Node::Element(Element::Markdown(MdElement::Text(text))) => text.as_ref(),
+        _ => return false,
+    };
+    let is_key = |node: NodeRef<'_, Node>| is_html_element(node, local_name!("kbd"));
+    text.trim() == "+"
+        && node.prev_sibling().is_some_and(is_key)
+        && node.next_sibling().is_some_and(is_key)
+}
+
+/// Whether `node` is part of a keyboard shortcut after its first key.
+fn continues_shortcut(node: NodeRef<'_, Node>) -> bool {
+    is_key_separator(node)
+        || (is_html_element(node, local_name!("kbd"))
+            && node.prev_sibling().is_some_and(is_key_separator))
+}
+
+/// Whether `element` is displayed as a block, by default or because it's configured to be.
+fn is_display_block(element: &HtmlElement, ctx: &pandoc::RenderContext<'_>) -> bool {
+    element.name.is_display_block()
+        || ctx
+            .block_elements
+            .iter()
+            .any(|name| *element.name.local == **name)
+        || (element.attrs.classes.split_whitespace())
+            .any(|class| ctx.block_classes.iter().any(|block| block == class))
+}
+
+/// Whether `node` is inline content, e.g. the text of an item in a tight list.
+fn is_inline(node: NodeRef<'_, Node>) -> bool {
+    match node.value() {
+        Node::Document => false,
+        Node::HtmlComment(_) | Node::HtmlText(_) => true,
+        Node::Element(Element::Html(element)) => !element.name.is_display_block(),
+        Node::Element(Element::Markdown(element)) => matches!(
+            element,
+            MdElement::Text(_)
+                | MdElement::SoftBreak
+                | MdElement::HardBreak
+                | MdElement::InlineCode(_)
+                | MdElement::TaskListMarker(_)
+                | MdElement::FootnoteReference(_)
+                | MdElement::Emphasis
+                | MdElement::Strong
+                | MdElement::Strikethrough
+                | MdElement::Link { .. }
+                | MdElement::Image { .. }
+        ),
+    }
+}
+
+/// Attributes of a `<div>`, marking admonitions (e.g. from `mdbook-admonish`) as notes for screen
+/// readers in HTML output unless they already have a role.
+fn admonition_attrs<'a>(
+    attrs: &'a Attributes,
+    output: &pandoc::OutputFormat,
+) -> Cow<'a, Attributes> {
+    const ROLE: QualName = html::name!("role");
+    let admonition = attrs
+        .classes
+        .split_whitespace()
+        .any(|class| class == "admonition");
+    if !matches!(output, pandoc::OutputFormat::HtmlLike)
+        || !admonition
+        || attrs.rest.contains_key(&ROLE)
+    {
+        return Cow::Borrowed(attrs);
+    }
+    let mut attrs = attrs.clone();
+    attrs.rest.insert(ROLE, "note".into());
+    Cow::Owned(attrs)
+}
+
+/// Whether `element` matches any of the class selectors (e.g. `.quiz`) or element names of
+/// interactive elements to strip from non-HTML output.
+fn is_interactive(element: &HtmlElement, selectors: &[String]) -> bool {
+    selectors
+        .iter()
+        .any(|selector| match selector.strip_prefix('.') {
+            Some(class) => (element.attrs.classes.split_whitespace()).any(|c| c == class),
+            None => *element.name.local == **selector,
+        })
+}
+
+/// The container an element named `name` belongs in (e.g. `<table>` for `<td>`), if `node` is such
+/// an element outside of one.
+fn orphaned_container(node: NodeRef<'_, Node>, name: &LocalName) -> Option<LocalName> {
+    let container = match *name {
+        local_name!("thead") | local_name!("th") | local_name!("tr") | local_name!("td") => {
+            local_name!("table")
+        }
+        local_name!("summary") => local_name!("details"),
+        local_name!("dt") | local_name!("dd") => local_name!("dl"),
+        _ => return None,
+    };
+    let contained = node
+        .ancestors()
+        .any(|ancestor| is_html_element(ancestor, container.clone()));
+    (!contained).then_some(container)
+}
+
+/// Whether `node` is a code block of the given kind, e.g. with the same info string.
+fn is_code_block(node: NodeRef<'_, Node>, kind: &CodeBlockKind<'_>) -> bool {
+    matches!(
+        node.value(),
+        Node::Element(Element::Markdown(MdElement::CodeBlock(other))) if other == kind
+    )
+}
+
+/// Whether `paragraph` is the chapter's first paragraph following its first heading.
+fn is_lead_paragraph(paragraph: NodeRef<'_, Node>) -> bool {
+    let is_heading = |node: &NodeRef<'_, Node>| {
+        matches!(
+            node.value(),
+            Node::Element(Element::Markdown(MdElement::Heading { .. }))
+        )
+    };
+    let is_paragraph = |node: &NodeRef<'_, Node>| {
+        matches!(
+            node.value(),
+            Node::Element(Element::Markdown(MdElement::Paragraph))
+        )
+    };
+    let top_level = (paragraph.parent())
+        .and_then(|parent| parent.parent())
+        .is_some_and(|node| matches!(node.value(), Node::Document));
+    top_level
+        && (paragraph.prev_siblings())
+            .find(|node| is_heading(node) || is_paragraph(node))
+            .filter(is_heading)
+            .is_some_and(|heading| !heading.prev_siblings().any(|node| is_heading(&node)))
+}
+
+/// Whether the list `item` is checked, if it is a task list item.
+fn task_list_marker(item: NodeRef<'_, Node<'_>>) -> Option<bool> {
+    let first = item.first_child()?;
+    // Items of loose lists wrap their text in a paragraph
+    let first = match first.value() {
+        Node::Element(Element::Markdown(MdElement::Paragraph)) => first.first_child()?,
+        _ => first,
+    };
+    match first.value() {
+        Node::Element(Element::Markdown(MdElement::TaskListMarker(checked))) => Some(*checked),
+        _ => None,
+    }
+}
+
+/// Whether `list` is a task list to render with checkboxes as its items' labels.
+fn is_checklist(list: NodeRef<'_, Node<'_>>, ctx: &pandoc::RenderContext<'_>) -> bool {
+    ctx.task_list_style == TaskListStyle::Checklist
+        && matches!(ctx.output, pandoc::OutputFormat::Latex { .. })
+        && matches!(
+            list.value(),
+            Node::Element(Element::Markdown(MdElement::List(None)))
+        )
+        && list_depth(list) <= latex::MAX_LIST_DEPTH
+        && list.children().any(|item| task_list_marker(item).is_some())
+}
+
+/// The image `paragraph` consists of, if it has alt text to caption it with and isn't already in a
+/// `<figure>`, which may have its own `<figcaption>`.
+fn captionable_image<'a, 'book>(
+    paragraph: NodeRef<'a, Node<'book>>,
+) -> Option<NodeRef<'a, Node<'book>>> {
+    let mut children = significant_children(paragraph);
+    let image = children.next().filter(|_| children.next().is_none())?;
+    let is_image = matches!(
+        image.value(),
+        Node::Element(Element::Markdown(MdElement::Image { .. }))
+    );
+    let in_figure =
+        (paragraph.ancestors()).any(|node| is_html_element(node, local_name!("figure")));
+    (is_image && image.has_children() && !in_figure).then_some(image)
+}
+
+/// The label of the code block in the same chapter that a link points to, if it is labelled as a
+/// listing.
+fn listing_reference<'a>(dest_url: &'a str, id_prefix: Option<&str>) -> Option<&'a str> {
+    dest_url
+        .strip_prefix('#')
+        .map(|id| {
+            id_prefix
+                .and_then(|prefix| id.strip_prefix(prefix))
+                .unwrap_or(id)
+        })
+        .filter(|id| id.starts_with(code::LISTING_LABEL_PREFIX))
+}
+
+/// The number of lists `list` is nested within, including itself.
+fn list_depth(list: NodeRef<'_, Node>) -> usize {
+    (iter::once(list).chain(list.ancestors()))
+        .filter(|node| {
+            matches!(
+                node.value(),
+                Node::Element(Element::Markdown(MdElement::List(_)))
+            )
+        })
+        .count()
+}
+
+/// The labelled panels of a tabbed content container, or `None` if they can't be matched up with
+/// labels.
+///
+/// Panels are the container's `<div>` children that don't hold the tabs' `<button>`s. Each is
+/// labelled by its `data-tabname` attribute, or else by the text of the corresponding button.
+fn tab_panels<'a, 'book>(
+    container: NodeRef<'a, Node<'book>>,
+) -> Option<Vec<(String, NodeRef<'a, Node<'book>>)>> {
+    let is_button = |node| is_html_element(node, local_name!("button"));
+    let (controls, panels): (Vec<_>, Vec<_>) =
+        significant_children(container).partition(|child| child.descendants().any(is_button));
+    let mut buttons = (controls.iter())
+        .flat_map(|control| control.descendants().filter(|&node| is_button(node)))
+        .map(|button| text_content(button).trim().to_owned());
+    let panels = (panels.into_iter())
+        .map(|panel| {
+            let Node::Element(Element::Html(element)) = panel.value() else {
+                return None;
+            };
+            if element.name.local != local_name!("div") {
+                return None;
+            }
+            let tab_name = (element.attrs.rest.iter())
+                .find_map(|(attr, value)| (&*attr.local == "data-tabname").then_some(value));
+            let label = match tab_name {
+                Some(name) => name.trim().to_owned(),
+                None => buttons.next()?,
+            };
+            Some((label, panel))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    (!panels.is_empty()).then_some(panels)
+}
+
+/// The level of the heading of the section containing `node`, if any.
+fn section_level(node: NodeRef<'_, Node>) -> Option<usize> {
+    (iter::once(node).chain(node.ancestors()))
+        .flat_map(|node| node.prev_siblings())
+        .find_map(|sibling| match sibling.value() {
+            Node::Element(Element::Markdown(MdElement::Heading { level, .. })) => {
+                Some(*level as usize)
+            }
+            _ => None,
+        })
+}
+
+fn is_html_element(node: NodeRef<'_, Node>, name: LocalName) -> bool {
+    matches!(node.value(), Node::Element(Element::Html(element)) if element.name.local == name)
 }
 
 struct DebugChildren<'event> {
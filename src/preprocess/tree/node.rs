@@ -21,7 +21,7 @@ pub enum Node<'book> {
     Element(Element<'book>),
 }
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct Attributes {
     pub id: Option<StrTendril>,
     pub classes: StrTendril,
@@ -46,13 +46,15 @@ pub enum MdElement<'a> {
     Paragraph,
     Text(CowStr<'a>),
     SoftBreak,
+    HardBreak,
     Heading {
         level: HeadingLevel,
         id: Option<CowStr<'a>>,
         classes: Vec<CowStr<'a>>,
         attrs: Vec<(CowStr<'a>, Option<CowStr<'a>>)>,
     },
-    BlockQuote,
+    /// A block quote, which is an admonition if it starts with a GitHub-style alert marker.
+    BlockQuote(Option<BlockQuoteKind>),
     InlineCode(CowStr<'a>),
     CodeBlock(CodeBlockKind<'a>),
     List(Option<u64>),
@@ -79,6 +81,54 @@ pub enum MdElement<'a> {
     },
 }
 
+/// The kind of an admonition written as a GitHub-style alert, e.g. `> [!NOTE]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockQuoteKind {
+    Note,
+    Tip,
+    Important,
+    Warning,
+    Caution,
+}
+
+impl BlockQuoteKind {
+    /// Parses an alert marker like `[!NOTE]`, ignoring case.
+    pub fn from_marker(marker: &str) -> Option<Self> {
+        let kind = marker.strip_prefix("[!")?.strip_suffix(']')?;
+        [
+            Self::Note,
+            Self::Tip,
+            Self::Important,
+            Self::Warning,
+            Self::Caution,
+        ]
+        .into_iter()
+        .find(|candidate| candidate.title().eq_ignore_ascii_case(kind))
+    }
+
+    /// Class of the `Div` the admonition is rendered as, matching Pandoc's `alerts` extension.
+    pub fn class(self) -> &'static str {
+        match self {
+            Self::Note => "note",
+            Self::Tip => "tip",
+            Self::Important => "important",
+            Self::Warning => "warning",
+            Self::Caution => "caution",
+        }
+    }
+
+    /// Title the admonition is rendered with.
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::Note => "Note",
+            Self::Tip => "Tip",
+            Self::Important => "Important",
+            Self::Warning => "Warning",
+            Self::Caution => "Caution",
+        }
+    }
+}
+
 pub trait QualNameExt {
     /// Is this the name of a [void element](https://developer.mozilla.org/en-US/docs/Glossary/Void_element)?
     fn is_void_element(&self) -> bool;
@@ -168,7 +218,7 @@ impl MdElement<'_> {
                 const SPAN: &QualName = &html::name!(html "span");
                 SPAN
             }
-            MdElement::SoftBreak => {
+            MdElement::SoftBreak | MdElement::HardBreak => {
                 const BR: &QualName = &html::name!(html "br");
                 BR
             }
@@ -218,7 +268,7 @@ impl MdElement<'_> {
                     HeadingLevel::H6 => H6,
                 }
             }
-            MdElement::BlockQuote => {
+            MdElement::BlockQuote(_) => {
                 const BLOCKQUOTE: &QualName = &html::name!(html "blockquote");
                 BLOCKQUOTE
             }
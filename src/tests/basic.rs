@@ -1,4 +1,13 @@
+use std::fs;
+
+use indoc::indoc;
+
 use super::{Chapter, Config, MDBook};
+use crate::{
+    archive::Archive, pandoc::AdmonitionStyle, AdmonitionsConfig, CopyAssets, EmptyChapters,
+    HardBreak, HorizontalRule, ListSpacing, MarkdownOutputConfig, OnChapterError, Sanitize,
+    TaskListStyle,
+};
 
 #[test]
 fn basic() {
@@ -19,6 +28,25 @@ fn basic() {
     ");
 }
 
+#[test]
+fn byte_order_mark() {
+    let book = MDBook::init()
+        .chapter(Chapter::new(
+            "Getting Started",
+            "\u{feff}# Getting Started",
+            "getting-started.md",
+        ))
+        .build();
+    insta::assert_snapshot!(book, @r"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/markdown/book.md    
+    ├─ markdown/book.md
+    │ # Getting Started {#book__markdown__src__getting-started.md__getting-started}
+    ");
+}
+
 #[test]
 fn strikethrough() {
     let book = MDBook::init()
@@ -64,3 +92,686 @@ fn task_lists() {
     │ [BulletList [[Plain [Str "\9746", Space, Str "Complete task"]], [Plain [Str "\9744", Space, Str "Incomplete task"]]]]
     "#);
 }
+
+#[test]
+fn checklist_task_lists() {
+    let book = MDBook::init()
+        .chapter(Chapter::new(
+            "",
+            "- [x] Complete task\n- [ ] Incomplete task\n- Not a task",
+            "chapter.md",
+        ))
+        .config(Config {
+            task_list_style: TaskListStyle::Checklist,
+            ..Config::latex()
+        })
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ \begin{itemize}[label=$\square$, leftmargin=*]
+    │ 
+    │ \item[$\boxtimes$]
+    │ 
+    │ Complete task
+    │ 
+    │ \item
+    │ 
+    │ Incomplete task
+    │ 
+    │ \item[]
+    │ 
+    │ Not a task
+    │ 
+    │ \end{itemize}
+    ├─ latex/src/chapter.md
+    │ [RawBlock (Format "latex") "\\begin{itemize}[label=$\\square$, leftmargin=*]", RawBlock (Format "latex") "\\item[$\\boxtimes$]", Plain [Str "Complete task"], RawBlock (Format "latex") "\\item", Plain [Str "Incomplete task"], RawBlock (Format "latex") "\\item[]", Plain [Str "Not a task"], RawBlock (Format "latex") "\\end{itemize}"]
+    "#);
+}
+
+#[test]
+fn lead_paragraph() {
+    let content =
+        "# Chapter\n\nFirst paragraph.\n\nSecond paragraph.\n\n## Section\n\nThird paragraph.";
+    let book = MDBook::init()
+        .chapter(Chapter::new("", content, "chapter.md"))
+        .config(Config {
+            lead_paragraph: true,
+            ..Config::html()
+        })
+        .build();
+    let html = fs::read_to_string(book.dir.join("html/book.html")).unwrap();
+    assert!(
+        html.contains("<div class=\"lead\">\n<p>First paragraph.</p>\n</div>"),
+        "{html}"
+    );
+    assert_eq!(html.matches("class=\"lead\"").count(), 1, "{html}");
+
+    let book = MDBook::init()
+        .chapter(Chapter::new("", content, "chapter.md"))
+        .config(Config {
+            lead_paragraph: true,
+            ..Config::latex()
+        })
+        .build();
+    let latex = fs::read_to_string(book.dir.join("latex/output.tex")).unwrap();
+    assert!(
+        latex.contains("\\begin{em}\n\nFirst paragraph.\n\n\\end{em}"),
+        "{latex}"
+    );
+    assert_eq!(latex.matches("\\begin{em}").count(), 1, "{latex}");
+}
+
+#[test]
+fn hard_breaks() {
+    let build = |hard_break| {
+        let book = MDBook::init()
+            .config(Config {
+                keep_preprocessed: true,
+                markdown: MarkdownOutputConfig {
+                    hard_break,
+                    ..Default::default()
+                },
+                ..Config::markdown()
+            })
+            .chapter(Chapter::new("", "Line one\\\nLine two", "chapter.md"))
+            .build();
+        let native = fs::read_to_string(book.dir.join("markdown/src/chapter.md")).unwrap();
+        let markdown = fs::read_to_string(book.dir.join("markdown/book.md")).unwrap();
+        (native, markdown)
+    };
+
+    let (native, markdown) = build(HardBreak::Linebreak);
+    assert!(
+        native.contains(r#"[Str "Line one", LineBreak, Str "Line two"]"#),
+        "{native}"
+    );
+    assert!(markdown.contains("Line one\\\nLine two"), "{markdown}");
+
+    let (native, markdown) = build(HardBreak::Backslash);
+    assert!(
+        native.contains("RawInline (Format \"markdown\") \"\\\\\n\""),
+        "{native}"
+    );
+    assert!(markdown.contains("Line one\\\nLine two"), "{markdown}");
+
+    let (native, markdown) = build(HardBreak::Spaces);
+    assert!(
+        native.contains("RawInline (Format \"markdown\") \"  \n\""),
+        "{native}"
+    );
+    assert!(markdown.contains("Line one  \nLine two"), "{markdown}");
+}
+
+#[test]
+fn horizontal_rule() {
+    let book = MDBook::init()
+        .config(Config::latex())
+        .chapter(Chapter::new("", "Before\n\n---\n\nAfter", "chapter.md"))
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ Before
+    │ 
+    │ \begin{center}\rule{0.5\linewidth}{0.5pt}\end{center}
+    │ 
+    │ After
+    ├─ latex/src/chapter.md
+    │ [Para [Str "Before"], HorizontalRule, Para [Str "After"]]
+    "#);
+}
+
+#[test]
+fn horizontal_rule_as_page_break() {
+    let book = MDBook::init()
+        .config(Config {
+            horizontal_rule: HorizontalRule::PageBreak,
+            ..Config::latex()
+        })
+        .chapter(Chapter::new("", "Before\n\n---\n\nAfter", "chapter.md"))
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ Before
+    │ 
+    │ \clearpage
+    │ 
+    │ After
+    ├─ latex/src/chapter.md
+    │ [Para [Str "Before"], RawBlock (Format "latex") "\\clearpage", Para [Str "After"]]
+    "#);
+}
+
+#[test]
+fn ignore_horizontal_rule() {
+    let book = MDBook::init()
+        .config(Config {
+            horizontal_rule: HorizontalRule::Ignore,
+            ..Config::latex()
+        })
+        .chapter(Chapter::new("", "Before\n\n---\n\nAfter", "chapter.md"))
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ Before
+    │ 
+    │ After
+    ├─ latex/src/chapter.md
+    │ [Para [Str "Before"], Para [Str "After"]]
+    "#);
+}
+
+#[test]
+fn dump_tree() {
+    let book = MDBook::init()
+        .config(Config {
+            dump_tree: true,
+            keep_preprocessed: false,
+            ..Config::latex()
+        })
+        .chapter(Chapter::new("", "Hello, world!", "nested/chapter.md"))
+        .build();
+    let tree = fs::read_to_string(book.dir.join("latex/trees/nested/chapter.tree")).unwrap();
+    assert!(tree.contains("Paragraph"), "unexpected tree dump: {tree}");
+}
+
+#[test]
+fn dump_command() {
+    let book = MDBook::init()
+        .config(Config {
+            dump_command: true,
+            ..Config::latex()
+        })
+        .chapter(Chapter::new("", "Hello, world!", "chapter.md"))
+        .build();
+    let command = fs::read_to_string(book.dir.join("latex/pandoc-command.sh")).unwrap();
+    assert!(command.contains("pandoc -f native"), "{command}");
+    assert!(command.contains("book/latex/src/chapter.md"), "{command}");
+    assert!(command.contains("pandoc-defaults.yaml"), "{command}");
+    let defaults = fs::read_to_string(book.dir.join("latex/pandoc-defaults.yaml")).unwrap();
+    assert!(defaults.contains("output-file:"), "{defaults}");
+}
+
+#[test]
+fn working_dir() {
+    let build = |working_dir: &str| {
+        let mut config = Config {
+            dump_command: true,
+            ..Config::latex()
+        };
+        config.profiles.get_mut("latex").unwrap().working_dir = Some(working_dir.into());
+        MDBook::init()
+            .config(config)
+            .file_in_root("templates/README", "")
+            .chapter(Chapter::new("", "Hello, world!", "chapter.md"))
+            .build()
+    };
+
+    let book = build("templates");
+    let command = fs::read_to_string(book.dir.join("latex/pandoc-command.sh")).unwrap();
+    let cd = command.lines().next().unwrap();
+    assert!(
+        cd.starts_with("cd ") && cd.ends_with("templates"),
+        "{command}"
+    );
+    assert!(
+        command.contains(" ../book/latex/src/chapter.md"),
+        "{command}"
+    );
+    assert!(book.dir.join("latex/output.tex").exists(), "{}", book.logs);
+
+    let book = build("missing");
+    assert!(
+        book.logs
+            .contains("Invalid `working-dir` in profile 'latex'"),
+        "{}",
+        book.logs
+    );
+}
+
+#[test]
+fn body_environment() {
+    let cfg = indoc! {r#"
+        [output.pandoc.profile.latex]
+        output-file = "output.tex"
+        standalone = false
+        body-environment = "mybook"
+    "#};
+    let book = MDBook::init()
+        .mdbook_config(cfg.parse().unwrap())
+        .chapter(Chapter::new("One", "# One", "one.md"))
+        .chapter(Chapter::new("Two", "# Two", "two.md"))
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/generated/body-begin.md
+    │ [RawBlock (Format "latex") "\\begin{mybook}"]
+    ├─ latex/generated/body-end.md
+    │ [RawBlock (Format "latex") "\\end{mybook}"]
+    ├─ latex/output.tex
+    │ \begin{mybook}
+    │ 
+    │ \chapter{One}\label{book__latex__src__one.md__one}
+    │ 
+    │ \chapter{Two}\label{book__latex__src__two.md__two}
+    │ 
+    │ \end{mybook}
+    ├─ latex/src/one.md
+    │ [Header 1 ("one", [], []) [Str "One"]]
+    ├─ latex/src/two.md
+    │ [Header 1 ("two", [], []) [Str "Two"]]
+    "#);
+}
+
+#[test]
+fn index() {
+    let book = MDBook::init()
+        .config(Config {
+            index: true,
+            ..Config::latex()
+        })
+        .chapter(Chapter::new(
+            "",
+            "# Rust\n\nRust{{#index Rust}} is a language{{#index 100% safe!}}.",
+            "chapter.md",
+        ))
+        // Generated files don't collide with the book's own
+        .file_in_src("printindex.md", "not a chapter")
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/generated/printindex.md
+    │ [RawBlock (Format "latex") "\\printindex"]
+    ├─ latex/output.tex
+    │ \chapter{Rust}\label{book__latex__src__chapter.md__rust}
+    │ 
+    │ Rust\index{Rust} is a language\index{100\% safe"!}.
+    │ 
+    │ \printindex
+    ├─ latex/src/chapter.md
+    │ [Header 1 ("rust", [], []) [Str "Rust"], Para [Str "Rust", RawInline (Format "latex") "\\index{Rust}", Str " is a language", RawInline (Format "latex") "\\index{100\\% safe\"!}", Str "."]]
+    ├─ latex/src/printindex.md
+    │ not a chapter
+    "#);
+}
+
+#[test]
+fn missing_chapter_file() {
+    let book = MDBook::init()
+        .chapter(Chapter::new("One", "# One", "one.md"))
+        .chapter(Chapter::new("Missing", "", "missing.md"))
+        .config(Config::latex());
+    fs::remove_file(book.book.source_dir().join("missing.md")).unwrap();
+    let book = book.build();
+    assert!(
+        book.logs.contains("Unable to read file") && book.logs.contains("of chapter 'Missing'"),
+        "{}",
+        book.logs
+    );
+    assert!(!book.logs.contains("of chapter 'One'"), "{}", book.logs);
+}
+
+#[test]
+fn empty_chapters() {
+    let book = MDBook::init()
+        .chapter(Chapter::new("One", "# One", "one.md"))
+        .chapter(Chapter::new("Draft", "\n  \n", "draft.md"))
+        .config(Config::latex())
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  WARN mdbook_pandoc::preprocess: Chapter 'Draft' is empty    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ \chapter{One}\label{book__latex__src__one.md__one}
+    ├─ latex/src/draft.md
+    │ []
+    ├─ latex/src/one.md
+    │ [Header 1 ("one", [], []) [Str "One"]]
+    "#);
+
+    let book = MDBook::init()
+        .chapter(Chapter::new("One", "# One", "one.md"))
+        .chapter(Chapter::new("Draft", "\n  \n", "draft.md"))
+        .config(Config {
+            empty_chapters: EmptyChapters::Skip,
+            ..Config::latex()
+        })
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  WARN mdbook_pandoc::preprocess: Skipping empty chapter 'Draft'    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ \chapter{One}\label{book__latex__src__one.md__one}
+    ├─ latex/src/one.md
+    │ [Header 1 ("one", [], []) [Str "One"]]
+    "#);
+}
+
+#[test]
+fn skip_chapters_that_fail_to_preprocess() {
+    // Block content inside an inline element can't be represented in Pandoc's AST
+    let malformed = "<span>\n\n# Heading\n\n</span>";
+    let book = MDBook::init()
+        .chapter(Chapter::new("One", "# One", "one.md"))
+        .chapter(Chapter::new("Malformed", malformed, "malformed.md"))
+        .chapter(Chapter::new("Two", "# Two", "two.md"))
+        .config(Config {
+            on_chapter_error: OnChapterError::Skip,
+            ..Config::latex()
+        })
+        .build();
+    assert!(book.logs.contains("Skipping chapter 'Malformed'"));
+    let latex = fs::read_to_string(book.dir.join("latex/output.tex")).unwrap();
+    assert!(latex.contains(r"\chapter{One}"), "{latex}");
+    assert!(latex.contains(r"\chapter{Two}"), "{latex}");
+
+    let book = MDBook::init()
+        .chapter(Chapter::new("One", "# One", "one.md"))
+        .chapter(Chapter::new("Malformed", malformed, "malformed.md"))
+        .config(Config::latex())
+        .build();
+    assert!(book
+        .logs
+        .contains("failed to preprocess chapter 'Malformed'"));
+    assert!(!book.dir.join("latex/output.tex").exists());
+}
+
+#[test]
+fn copy_referenced_assets() {
+    let content = indoc! {r#"
+        ![Logo](images/logo.png)
+
+        <video src="media/clip.mp4"></video>
+    "#};
+    let book = MDBook::init()
+        .chapter(Chapter::new("", content, "chapter.md"))
+        .file_in_src("images/logo.png", "logo")
+        .file_in_src("media/clip.mp4", "clip")
+        .file_in_src("unused/large.bin", &"0".repeat(1 << 20))
+        .config(Config {
+            copy_assets: CopyAssets::Referenced,
+            ..Config::latex()
+        })
+        .build();
+    let preprocessed = book.dir.join("latex/src");
+    assert!(preprocessed.join("images/logo.png").is_file());
+    assert!(preprocessed.join("media/clip.mp4").is_file());
+    assert!(!preprocessed.join("unused").exists());
+
+    let book = MDBook::init()
+        .chapter(Chapter::new("", content, "chapter.md"))
+        .file_in_src("unused/large.bin", &"0".repeat(1 << 20))
+        .config(Config::latex())
+        .build();
+    assert!(book.dir.join("latex/src/unused/large.bin").is_file());
+}
+
+#[test]
+fn extremely_deep_list() {
+    let list = (0..30)
+        .map(|depth| format!("{}- level {depth}\n", "  ".repeat(depth)))
+        .collect::<String>();
+    let cfg = indoc! {r#"
+        [book]
+        title = "Book"
+
+        [output.pandoc.profile.latex]
+        output-file = "book.tex"
+    "#};
+    let book = MDBook::init()
+        .mdbook_config(cfg.parse().unwrap())
+        .chapter(Chapter::new("", list, "chapter.md"))
+        .build();
+    assert!(book.logs.contains(
+        "Lists are nested 30 levels deep, but LaTeX output supports at most 20; \
+        flattening more deeply nested lists"
+    ));
+    let latex = fs::read_to_string(book.dir.join("latex/book.tex")).unwrap();
+    assert!(latex.contains(r"\setlistdepth{20}"));
+    assert!(latex.contains("level 29"));
+}
+
+#[test]
+fn list_spacing() {
+    let build = |list_spacing, content: &str| {
+        let book = MDBook::init()
+            .chapter(Chapter::new("", content, "chapter.md"))
+            .config(Config {
+                list_spacing,
+                ..Config::latex()
+            })
+            .build();
+        let native = fs::read_to_string(book.dir.join("latex/src/chapter.md")).unwrap();
+        let latex = fs::read_to_string(book.dir.join("latex/output.tex")).unwrap();
+        (native, latex)
+    };
+    let tight = "- one\n- two\n";
+    let loose = "- one\n\n- two\n\n  more\n";
+
+    let (native, latex) = build(ListSpacing::Auto, tight);
+    insta::assert_snapshot!(native, @r#"[BulletList [[Plain [Str "one"]], [Plain [Str "two"]]]]"#);
+    assert!(latex.contains(r"\tightlist"));
+    let (native, latex) = build(ListSpacing::Auto, loose);
+    insta::assert_snapshot!(native, @r#"[BulletList [[Para [Str "one"]], [Para [Str "two"], Para [Str "more"]]]]"#);
+    assert!(!latex.contains(r"\tightlist"));
+
+    let (native, latex) = build(ListSpacing::Tight, loose);
+    insta::assert_snapshot!(native, @r#"[BulletList [[Plain [Str "one"]], [Plain [Str "two"], Plain [Str "more"]]]]"#);
+    assert!(latex.contains(r"\tightlist"));
+
+    let (native, latex) = build(ListSpacing::Loose, tight);
+    insta::assert_snapshot!(native, @r#"[BulletList [[Para [Str "one"]], [Para [Str "two"]]]]"#);
+    assert!(!latex.contains(r"\tightlist"));
+}
+
+#[test]
+#[cfg(unix)]
+fn symlink_loop_in_source_dir() {
+    let book = MDBook::init()
+        .chapter(Chapter::new("Chapter", "# Chapter", "chapter.md"))
+        .symlink_in_src("dir/loop", "..")
+        .build();
+    insta::assert_snapshot!(book, @r"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  WARN mdbook_pandoc::preprocess: Skipping symlink '$ROOT/src/dir/loop' since it points to one of its parent directories    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/markdown/book.md    
+    ├─ markdown/book.md
+    │ # Chapter {#book__markdown__src__chapter.md__chapter}
+    ");
+}
+
+#[test]
+fn acronyms() {
+    let cfg = indoc! {r#"
+        [book]
+        title = "Book"
+
+        [output.pandoc.acronyms]
+        file = "acronyms.toml"
+
+        [output.pandoc.profile.latex]
+        output-file = "book.tex"
+    "#};
+    let book = MDBook::init()
+        .mdbook_config(cfg.parse().unwrap())
+        .file_in_root(
+            "acronyms.toml",
+            indoc! {r#"
+                GPU = "Graphics Processing Unit"
+                TLS = "Transport Layer Security"
+            "#},
+        )
+        .chapter(Chapter::new(
+            "",
+            "# Chapter\n\nThe <abbr>GPU</abbr> speaks <abbr>TLS</abbr>, not <abbr>SSL</abbr>.",
+            "chapter.md",
+        ))
+        .build();
+    let latex = fs::read_to_string(book.dir.join("latex/book.tex")).unwrap();
+    assert!(latex.contains(r"\usepackage{glossaries}"), "{latex}");
+    assert!(latex.contains(r"\makeglossaries"), "{latex}");
+    assert!(latex.contains(r"\newacronym{GPU}{GPU}{Graphics Processing Unit}"));
+    assert!(latex.contains(r"\newacronym{TLS}{TLS}{Transport Layer Security}"));
+    assert!(
+        latex.contains(r"The \gls{GPU} speaks \gls{TLS}, not"),
+        "{latex}"
+    );
+    assert!(!latex.contains(r"\gls{SSL}"), "{latex}");
+    let (body, end) = latex.split_once(r"\printglossaries").unwrap();
+    assert!(body.contains(r"\gls{TLS}") && end.contains(r"\end{document}"));
+}
+
+#[test]
+fn archive() {
+    let build = |archive| {
+        MDBook::init()
+            .config(Config {
+                archive,
+                ..Config::latex()
+            })
+            .chapter(Chapter::new("", "Hello, world!", "chapter.md"))
+            .build()
+    };
+
+    let book = build(Archive::Zip);
+    let zip = zip::ZipArchive::new(fs::File::open(book.dir.join("book.zip")).unwrap()).unwrap();
+    let mut files = zip.file_names().collect::<Vec<_>>();
+    files.sort_unstable();
+    assert!(files.contains(&"latex/output.tex"), "{files:?}");
+    assert!(files.contains(&"latex/src/chapter.md"), "{files:?}");
+    assert!(!book.dir.join("book.tar.gz").exists());
+
+    let book = build(Archive::TarGz);
+    let tar_gz = fs::File::open(book.dir.join("book.tar.gz")).unwrap();
+    let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(tar_gz));
+    let files = (tar.entries().unwrap())
+        .map(|entry| entry.unwrap().path().unwrap().into_owned())
+        .collect::<Vec<_>>();
+    assert!(files.contains(&"latex/output.tex".into()), "{files:?}");
+    assert!(!book.dir.join("book.zip").exists());
+
+    let book = build(Archive::None);
+    assert!(!book.dir.join("book.zip").exists());
+    assert!(!book.dir.join("book.tar.gz").exists());
+}
+
+#[test]
+fn sanitize() {
+    let build = |sanitize| {
+        let book = MDBook::init()
+            .config(Config {
+                markdown: MarkdownOutputConfig {
+                    sanitize,
+                    ..Default::default()
+                },
+                ..Config::latex()
+            })
+            .chapter(Chapter::new(
+                "",
+                "zero\u{200b}width and\u{a0}non-breaking",
+                "chapter.md",
+            ))
+            .build();
+        fs::read_to_string(book.dir.join("latex/src/chapter.md")).unwrap()
+    };
+
+    let chapter = build(vec![]);
+    assert!(chapter.contains("zero\u{200b}width"), "{chapter}");
+
+    let chapter = build(vec![Sanitize::ZeroWidth]);
+    assert!(
+        chapter.contains("zerowidth and\u{a0}non-breaking"),
+        "{chapter}"
+    );
+
+    let chapter = build(vec![Sanitize::ZeroWidth, Sanitize::NonBreakingSpace]);
+    assert!(chapter.contains("zerowidth and non-breaking"), "{chapter}");
+}
+
+#[test]
+fn admonition_styles() {
+    let content = indoc! {"
+        > [!NOTE]
+        > Some *text*
+
+        > [!warning]
+
+        > [!TIP] with a title isn't an admonition
+
+        > Just a quote
+    "};
+    let build = |style| {
+        let book = MDBook::init()
+            .config(Config {
+                admonitions: AdmonitionsConfig { style },
+                ..Config::latex()
+            })
+            .chapter(Chapter::new("", content, "chapter.md"))
+            .build();
+        fs::read_to_string(book.dir.join("latex/src/chapter.md")).unwrap()
+    };
+    insta::assert_snapshot!(build(AdmonitionStyle::Div), @r#"[Div ("", ["note"], []) [Div ("", ["title"], []) [Para [Str "Note"]], Para [Str "Some ", Emph [Str "text"]]], Div ("", ["warning"], []) [Div ("", ["title"], []) [Para [Str "Warning"]]], BlockQuote [Para [Str "[", Str "!TIP", Str "]", Str " with a title isn't an admonition"]], BlockQuote [Para [Str "Just a quote"]]]"#);
+    let blockquotes = build(AdmonitionStyle::Blockquote);
+    insta::assert_snapshot!(blockquotes, @r#"[BlockQuote [Para [Strong [Str "Note"]], Para [Str "Some ", Emph [Str "text"]]], BlockQuote [Para [Strong [Str "Warning"]]], BlockQuote [Para [Str "[", Str "!TIP", Str "]", Str " with a title isn't an admonition"]], BlockQuote [Para [Str "Just a quote"]]]"#);
+
+    // Profiles can override the style
+    let cfg = indoc! {r#"
+        [output.pandoc.admonitions]
+        style = "div"
+
+        [output.pandoc.profile.latex]
+        output-file = "output.tex"
+        standalone = false
+        admonition-style = "blockquote"
+    "#};
+    let book = MDBook::init()
+        .mdbook_config(cfg.parse().unwrap())
+        .chapter(Chapter::new("", content, "chapter.md"))
+        .build();
+    let chapter = fs::read_to_string(book.dir.join("latex/src/chapter.md")).unwrap();
+    assert_eq!(chapter, blockquotes);
+}
+
+#[test]
+#[ignore]
+fn summarize_latex_log() {
+    let mut config = Config::pdf();
+    config.profiles.get_mut("pdf").unwrap().summarize_latex_log = true;
+    // Digits can't be hyphenated, so a long enough number can't fit on a line
+    let unbreakable = "1".repeat(200);
+    let book = MDBook::init()
+        .config(config)
+        .chapter(Chapter::new("", unbreakable, "chapter.md"))
+        .build();
+    assert!(book.logs.contains("overfull boxes"), "{}", book.logs);
+    assert!(book.logs.contains(r"Overfull \hbox"), "{}", book.logs);
+}
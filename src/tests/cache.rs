@@ -0,0 +1,98 @@
+use std::{cell::Cell, fs};
+
+use tempfile::TempDir;
+
+use crate::cache::Cache;
+
+#[test]
+fn reuse_cached_files() {
+    let root = TempDir::new().unwrap();
+    let runs = Cell::new(0);
+    let render = |cache: &Cache, source: &str| {
+        cache
+            .get_or_create("stub", source, "txt", |path| {
+                runs.set(runs.get() + 1);
+                fs::write(path, source.to_uppercase())?;
+                Ok(())
+            })
+            .unwrap()
+    };
+
+    let cache = Cache::new(root.path().join("book/.cache"), root.path());
+    let path = render(&cache, "graph");
+    assert!(path.starts_with("book/.cache"), "{}", path.display());
+    assert_eq!(
+        fs::read_to_string(root.path().join(&path)).unwrap(),
+        "GRAPH"
+    );
+    assert_eq!(runs.get(), 1);
+
+    // A later build reuses the file cached for the same input
+    let cache = Cache::new(root.path().join("book/.cache"), root.path());
+    assert_eq!(render(&cache, "graph"), path);
+    assert_eq!(runs.get(), 1);
+
+    // Different inputs are cached separately
+    assert_ne!(render(&cache, "other graph"), path);
+    assert_eq!(runs.get(), 2);
+
+    // Failures leave nothing behind to be mistaken for a cached file
+    let failed = cache.get_or_create("stub", "broken", "txt", |path| {
+        fs::write(path, "partial")?;
+        anyhow::bail!("failed")
+    });
+    assert!(failed.is_err());
+    assert_eq!(
+        fs::read_dir(root.path().join("book/.cache"))
+            .unwrap()
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn evict_unused_files() {
+    let root = TempDir::new().unwrap();
+    let render = |cache: &Cache, source: &str| {
+        cache
+            .get_or_create("stub", source, "txt", |path| {
+                fs::write(path, source)?;
+                Ok(())
+            })
+            .unwrap()
+    };
+
+    let cache = Cache::new(root.path().join("book/.cache"), root.path());
+    let kept = render(&cache, "kept");
+    let removed = render(&cache, "removed");
+    cache.evict_unused().unwrap();
+    assert!(root.path().join(&kept).exists());
+    assert!(root.path().join(&removed).exists());
+
+    // Files no longer used by a later build are evicted once it finishes
+    let cache = Cache::new(root.path().join("book/.cache"), root.path());
+    assert_eq!(render(&cache, "kept"), kept);
+    cache.evict_unused().unwrap();
+    assert!(root.path().join(&kept).exists());
+    assert!(!root.path().join(&removed).exists());
+
+    // Builds that never create the cache directory have nothing to evict
+    let cache = Cache::new(root.path().join("missing"), root.path());
+    cache.evict_unused().unwrap();
+}
+
+#[test]
+fn stable_file_names() {
+    let root = TempDir::new().unwrap();
+    let cache = Cache::new(root.path().join("book/.cache"), root.path());
+    let path = cache
+        .get_or_create("stub", "graph", "txt", |path| {
+            fs::write(path, "")?;
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(
+        path.file_name().unwrap(),
+        "stub-eef93e1d14482804277fca0172464032d1a4fdbcc338524059fa1e861454ad4d.txt"
+    );
+}
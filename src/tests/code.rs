@@ -1,5 +1,9 @@
+use std::collections::HashMap;
+
 use indoc::indoc;
 
+use crate::UnknownLanguage;
+
 use super::{Chapter, CodeConfig, Config, MDBook};
 
 #[test]
@@ -106,6 +110,7 @@ fn code_block_with_hidden_lines() {
         .config(Config {
             code: CodeConfig {
                 show_hidden_lines: true,
+                ..Default::default()
             },
             ..Config::markdown()
         })
@@ -170,6 +175,7 @@ fn non_rust_code_block_with_hidden_lines() {
         .config(Config {
             code: CodeConfig {
                 show_hidden_lines: true,
+                ..Default::default()
             },
             ..Config::markdown()
         })
@@ -191,6 +197,42 @@ fn non_rust_code_block_with_hidden_lines() {
     ");
 }
 
+#[test]
+fn trim_blank_lines_around_code_block() {
+    let content = indoc! {r#"
+        ```text
+
+
+        foo
+
+        bar
+
+        ```
+    "#};
+    let book = MDBook::init()
+        .config(Config {
+            code: CodeConfig {
+                trim_blank_lines: true,
+                ..Default::default()
+            },
+            ..Config::markdown()
+        })
+        .chapter(Chapter::new("", content, "chapter.md"))
+        .build();
+    insta::assert_snapshot!(book, @r"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/markdown/book.md    
+    ├─ markdown/book.md
+    │ ``` text
+    │ foo
+    │ 
+    │ bar
+    │ ```
+    ");
+}
+
 #[test]
 fn code_block_hidelines_override() {
     let content = indoc! {r#"
@@ -265,6 +307,186 @@ fn code_block_with_very_long_line_with_special_characters() {
     ");
 }
 
+#[test]
+fn styled_diffs() {
+    let content = indoc! {"
+        ```diff
+        @@ -1,2 +1,2 @@
+         context
+        -old line
+        +new line
+        ```
+    "};
+    let book = MDBook::init()
+        .config(Config {
+            code: CodeConfig {
+                style_diffs: true,
+                ..Default::default()
+            },
+            ..Config::latex()
+        })
+        .chapter(Chapter::new("", content, "chapter.md"))
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ \begin{lstlisting}[basicstyle=\ttfamily, columns=fullflexible, escapechar=`]
+    │ `\color{blue}`@@ -1,2 +1,2 @@
+    │ `\color{black}` context
+    │ `\color{red}`-old line
+    │ `\color{green!50!black}`+new line
+    │ \end{lstlisting}
+    ├─ latex/src/chapter.md
+    │ [RawBlock (Format "latex") "\\begin{lstlisting}[basicstyle=\\ttfamily, columns=fullflexible, escapechar=`]
+    │ `\\color{blue}`@@ -1,2 +1,2 @@
+    │ `\\color{black}` context
+    │ `\\color{red}`-old line
+    │ `\\color{green!50!black}`+new line
+    │ \\end{lstlisting}"]
+    "#);
+}
+
+#[test]
+fn code_is_not_smartened() {
+    let content = indoc! {r#"
+        Call `frobnicate(a -- b, ...)`:
+
+        ```rust
+        // ... "quoted" -- and more
+        ```
+    "#};
+    let book = MDBook::init()
+        .config(Config::markdown())
+        .chapter(Chapter::new("", content, "chapter.md"))
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/markdown/book.md    
+    ├─ markdown/book.md
+    │ Call `frobnicate(a -- b, ...)`:
+    │ 
+    │ ``` rust
+    │ // ... "quoted" -- and more
+    │ ```
+    "#);
+}
+
+#[test]
+fn very_long_inline_code() {
+    let content = "Call `a_very_long_function_name_that_does_not_fit_on_one_line()` or `short()`.";
+    let book = MDBook::init()
+        .config(Config::latex())
+        .chapter(Chapter::new("", content, "chapter.md"))
+        .build();
+    let chapter = std::fs::read_to_string(book.dir.join("latex/src/chapter.md")).unwrap();
+    insta::assert_snapshot!(chapter, @r#"[Para [Str "Call ", Code ("", [], []) "a_very_long_function_name_that_does_not_fit_on_one_line()", Str " or ", Code ("", [], []) "short()", Str "."]]"#);
+
+    let book = MDBook::init()
+        .config(Config {
+            code: CodeConfig {
+                break_inline_code_longer_than: Some(40),
+                ..Default::default()
+            },
+            ..Config::latex()
+        })
+        .chapter(Chapter::new("", content, "chapter.md"))
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ Call
+    │ \texttt{\seqsplit{a\_very\_long\_function\_name\_that\_does\_not\_fit\_on\_one\_line()}}
+    │ or \texttt{short()}.
+    ├─ latex/src/chapter.md
+    │ [Para [Str "Call ", RawInline (Format "latex") "\\texttt{\\seqsplit{a\\_very\\_long\\_function\\_name\\_that\\_does\\_not\\_fit\\_on\\_one\\_line()}}", Str " or ", Code ("", [], []) "short()", Str "."]]
+    "#);
+}
+
+#[test]
+fn unknown_language() {
+    let content = indoc! {"
+        Before
+
+        ```dot
+        digraph { a -> b }
+        ```
+
+        ```rust
+        fn main() {}
+        ```
+
+        After
+    "};
+    let build = |unknown_language| {
+        MDBook::init()
+            .config(Config {
+                code: CodeConfig {
+                    unknown_language,
+                    ..Default::default()
+                },
+                ..Config::markdown()
+            })
+            .chapter(Chapter::new("", content, "chapter.md"))
+            .build()
+    };
+    insta::assert_snapshot!(build(UnknownLanguage::Passthrough), @r"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/markdown/book.md    
+    ├─ markdown/book.md
+    │ Before
+    │ 
+    │ ``` dot
+    │ digraph { a -> b }
+    │ ```
+    │ 
+    │ ``` rust
+    │ fn main() {}
+    │ ```
+    │ 
+    │ After
+    ");
+    insta::assert_snapshot!(build(UnknownLanguage::Text), @r"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/markdown/book.md    
+    ├─ markdown/book.md
+    │ Before
+    │ 
+    │     digraph { a -> b }
+    │ 
+    │ ``` rust
+    │ fn main() {}
+    │ ```
+    │ 
+    │ After
+    ");
+    insta::assert_snapshot!(build(UnknownLanguage::Drop), @r"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/markdown/book.md    
+    ├─ markdown/book.md
+    │ Before
+    │ 
+    │ ``` rust
+    │ fn main() {}
+    │ ```
+    │ 
+    │ After
+    ");
+}
+
 #[test]
 fn mdbook_rust_code_block_attributes() {
     let book = MDBook::init()
@@ -333,3 +555,264 @@ fn regression_inline_code_newline() {
     │ ]
     "#);
 }
+
+#[test]
+fn code_block_filenames() {
+    let content = indoc! {r#"
+        ```rust,filename=src/main.rs
+        fn main() {}
+        ```
+    "#};
+    let book = MDBook::init()
+        .config(Config::latex())
+        .chapter(Chapter::new("", content, "chapter.md"))
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ \noindent\textbf{\texttt{src/main.rs}}\par\nopagebreak
+    │ 
+    │ \begin{Shaded}
+    │ \begin{Highlighting}[]
+    │ \KeywordTok{fn}\NormalTok{ main() }\OperatorTok{\{\}}
+    │ \end{Highlighting}
+    │ \end{Shaded}
+    ├─ latex/src/chapter.md
+    │ [RawBlock (Format "latex") "\\noindent\\textbf{\\texttt{src/main.rs}}\\par\\nopagebreak", CodeBlock ("", ["rust"], []) "fn main() {}
+    │ "]
+    "#);
+
+    let content = indoc! {r#"
+        ```toml title="My Cargo.toml"
+        [package]
+        ```
+    "#};
+    let book = MDBook::init()
+        .config(Config::pandoc())
+        .chapter(Chapter::new("", content, "chapter.md"))
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/markdown/pandoc-ir    
+    ├─ markdown/pandoc-ir
+    │ [ Div
+    │     ( "" , [ "filename" ] , [] )
+    │     [ Plain [ Code ( "" , [] , [] ) "My Cargo.toml" ] ]
+    │ , CodeBlock ( "" , [ "toml" ] , [] ) "[package]\n"
+    │ ]
+    "#);
+}
+
+#[test]
+fn language_aliases() {
+    let content = indoc! {"
+        ```rs
+        # fn hidden() {}
+        fn main() {}
+        ```
+
+        ```sh
+        echo hi
+        ```
+    "};
+    let book = MDBook::init()
+        .config(Config {
+            code: CodeConfig {
+                language_aliases: HashMap::from([
+                    ("rs".into(), "rust".into()),
+                    ("sh".into(), "bash".into()),
+                ]),
+                ..Default::default()
+            },
+            ..Config::latex()
+        })
+        .chapter(Chapter::new("", content, "chapter.md"))
+        .build();
+    let chapter = std::fs::read_to_string(book.dir.join("latex/src/chapter.md")).unwrap();
+    insta::assert_snapshot!(chapter, @r#"
+    [CodeBlock ("", ["rust"], []) "fn main() {}
+    ", CodeBlock ("", ["bash"], []) "echo hi
+    "]
+    "#);
+}
+
+#[test]
+fn console_language() {
+    let content = indoc! {"
+        ```console
+        $ echo hi
+        hi
+        ```
+
+        ```shell-session
+        $ true
+        ```
+    "};
+    let book = MDBook::init()
+        .config(Config {
+            code: CodeConfig {
+                console_language: Some("bash".into()),
+                ..Default::default()
+            },
+            ..Config::latex()
+        })
+        .chapter(Chapter::new("", content, "chapter.md"))
+        .build();
+    let chapter = std::fs::read_to_string(book.dir.join("latex/src/chapter.md")).unwrap();
+    insta::assert_snapshot!(chapter, @r#"
+    [CodeBlock ("", ["bash"], []) "$ echo hi
+    hi
+    ", CodeBlock ("", ["bash"], []) "$ true
+    "]
+    "#);
+}
+
+#[test]
+fn listing_references() {
+    let content = indoc! {r#"
+        See Listing [](#lst:main), or [the listing](#lst:main).
+
+        ```rust {#lst:main}
+        fn main() {}
+        ```
+    "#};
+    let book = MDBook::init()
+        .config(Config::latex())
+        .chapter(Chapter::new("", content, "chapter.md"))
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ See Listing \ref{chapter-lst:main}, or \hyperref[chapter-lst:main]{the listing}.
+    │ 
+    │ \captionof{listing}{}\label{chapter-lst:main}\nopagebreak
+    │ 
+    │ \begin{Shaded}
+    │ \begin{Highlighting}[]
+    │ \KeywordTok{fn}\NormalTok{ main() }\OperatorTok{\{\}}
+    │ \end{Highlighting}
+    │ \end{Shaded}
+    ├─ latex/src/chapter.md
+    │ [Para [Str "See Listing ", RawInline (Format "latex") "\\ref{chapter-lst:main}", Str ", or ", RawInline (Format "latex") "\\hyperref[chapter-lst:main]{", Str "the listing", RawInline (Format "latex") "}", Str "."], RawBlock (Format "latex") "\\captionof{listing}{}\\label{chapter-lst:main}\\nopagebreak", CodeBlock ("", ["rust"], []) "fn main() {}
+    │ "]
+    "#);
+}
+
+#[test]
+fn listings_with_filenames_in_multiple_chapters() {
+    let book = MDBook::init()
+        .config(Config::latex())
+        .chapter(Chapter::new(
+            "",
+            indoc! {r#"
+                See Listing [](#lst:main).
+
+                ```rust,filename=src/main_1.rs {#lst:main}
+                fn main() {}
+                ```
+            "#},
+            "one.md",
+        ))
+        .chapter(Chapter::new(
+            "",
+            indoc! {r#"
+                ```rust {#lst:main}
+                fn two() {}
+                ```
+            "#},
+            "two.md",
+        ))
+        .build();
+    let output = std::fs::read_to_string(book.dir.join("latex/output.tex")).unwrap();
+    insta::assert_snapshot!(output, @r"
+    See Listing \ref{one-lst:main}.
+
+    \captionof{listing}{\texttt{src/main\_1.rs}}\label{one-lst:main}\nopagebreak
+
+    \begin{Shaded}
+    \begin{Highlighting}[]
+    \KeywordTok{fn}\NormalTok{ main() }\OperatorTok{\{\}}
+    \end{Highlighting}
+    \end{Shaded}
+
+    \captionof{listing}{}\label{two-lst:main}\nopagebreak
+
+    \begin{Shaded}
+    \begin{Highlighting}[]
+    \KeywordTok{fn}\NormalTok{ two() }\OperatorTok{\{\}}
+    \end{Highlighting}
+    \end{Shaded}
+    ");
+}
+
+#[test]
+fn merge_adjacent() {
+    let content = indoc! {r#"
+        ```rust
+        fn one() {}
+        ```
+        ```rust
+        fn two() {}
+        ```
+
+        ```toml
+        [package]
+        ```
+    "#};
+    let book = MDBook::init()
+        .config(Config {
+            code: CodeConfig {
+                merge_adjacent: true,
+                ..Default::default()
+            },
+            ..Config::markdown()
+        })
+        .chapter(Chapter::new("", content, "chapter.md"))
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/markdown/book.md    
+    ├─ markdown/book.md
+    │ ``` rust
+    │ fn one() {}
+    │ fn two() {}
+    │ ```
+    │ 
+    │ ``` toml
+    │ [package]
+    │ ```
+    "#);
+}
+
+#[test]
+fn graphviz() {
+    let content = indoc! {"
+        ```dot
+        digraph { a -> b }
+        ```
+    "};
+    let book = MDBook::init()
+        .config(Config {
+            code: CodeConfig {
+                graphviz: true,
+                ..Default::default()
+            },
+            ..Config::latex()
+        })
+        .chapter(Chapter::new("", content, "chapter.md"))
+        .build();
+    let chapter = std::fs::read_to_string(book.dir.join("latex/src/chapter.md")).unwrap();
+    assert!(chapter.starts_with("[Plain [Image"), "{chapter}");
+    assert!(chapter.contains(".pdf\", \"\")"), "{chapter}");
+    assert!(!chapter.contains("CodeBlock"), "{chapter}");
+}
@@ -2,7 +2,7 @@ use std::str::FromStr;
 
 use indoc::indoc;
 
-use super::MDBook;
+use super::{Chapter, MDBook};
 
 #[test]
 fn disabled() {
@@ -70,88 +70,683 @@ fn raw_opts() {
         indent = true
         colorlinks = false
     "#};
-    let output = MDBook::options()
-        .max_log_level(tracing::Level::TRACE)
-        .init()
+    // Only the entries set by the book's and profile's configuration, which are passed through
+    let defaults = pandoc_defaults(cfg);
+    let relevant = [
+        "variables",
+        "metadata",
+        "fail-if-warnings",
+        "resource-path",
+        "verbosity",
+    ]
+    .into_iter()
+    .map(|key| (key.into(), defaults[key].clone()))
+    .collect::<serde_yaml::Mapping>();
+    insta::assert_snapshot!(serde_yaml::to_string(&relevant).unwrap(), @r"
+    variables:
+      colorlinks: false
+      dir: ltr
+      header-includes:
+      - text1
+      - text2
+      indent: true
+      lang: en
+    metadata:
+      author:
+      - John Doe
+      - Jane Doe
+      description: The example book covers examples.
+      title: Example book
+    fail-if-warnings: false
+    resource-path:
+    - really-long-path
+    - really-long-path2
+    verbosity: INFO
+    ");
+}
+
+#[test]
+fn toc_position() {
+    let defaults = pandoc_defaults(indoc! {r#"
+        [output.pandoc.profile.test]
+        output-file = "/dev/null"
+        to = "latex"
+        toc-position = "before-body"
+
+        [output.pandoc.profile.test.variables]
+        include-before = ["abstract"]
+    "#});
+    // Pandoc's own table of contents is replaced by one placed before the body
+    assert_eq!(defaults["table-of-contents"], false);
+    insta::assert_snapshot!(strings(&defaults["variables"]["include-before"]), @r"
+    abstract
+
+    \tableofcontents
+    ");
+}
+
+#[test]
+fn number_within_chapters() {
+    let defaults = pandoc_defaults(indoc! {r#"
+        [output.pandoc.profile.test]
+        output-file = "/dev/null"
+        to = "latex"
+        number-within-chapters = true
+    "#});
+    insta::assert_snapshot!(strings(&defaults["variables"]["header-includes"]), @r"
+    \IfFileExists{fvextra.sty}{% use fvextra if available to break long lines in code blocks
+      \usepackage{fvextra}
+      \fvset{breaklines}
+    }{}
+
+    \counterwithin{figure}{chapter}
+    \counterwithin{table}{chapter}
+    ");
+}
+
+#[test]
+fn number_within_sections() {
+    let cfg = indoc! {r#"
+        [output.pandoc.profile.test]
+        output-file = "output.tex"
+        number-within-chapters = true
+        variables.documentclass = "article"
+    "#};
+    let content = indoc! {r#"
+        ```rust {#lst:main}
+        fn main() {}
+        ```
+    "#};
+    let book = MDBook::init()
         .mdbook_config(mdbook::Config::from_str(cfg).unwrap())
+        .chapter(Chapter::new("", content, "chapter.md"))
+        .build();
+    let latex = std::fs::read_to_string(book.dir.join("test/output.tex")).unwrap();
+    let numbering = latex
+        .lines()
+        .filter(|line| line.contains("within"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    insta::assert_snapshot!(numbering, @r"
+    \counterwithin{figure}{section}
+    \counterwithin{table}{section}
+    \DeclareFloatingEnvironment[name=Listing, listname={List of Listings}, within=section]{listing}
+    ");
+}
+
+#[test]
+fn page_footer() {
+    let defaults = pandoc_defaults(indoc! {r#"
+        [book]
+        authors = ["John Doe", "Jane Doe"]
+
+        [output.pandoc.profile.test]
+        output-file = "/dev/null"
+        to = "latex"
+        page-footer = true
+    "#});
+    insta::assert_snapshot!(strings(&defaults["variables"]["header-includes"]), @r"
+    \IfFileExists{fvextra.sty}{% use fvextra if available to break long lines in code blocks
+      \usepackage{fvextra}
+      \fvset{breaklines}
+    }{}
+
+    \usepackage{fancyhdr}
+
+    \pagestyle{fancy}
+    \fancyhf{}
+    \fancyfoot[L]{John Doe, Jane Doe \hfill \today}
+    \fancyfoot[R]{\thepage}
+    \renewcommand{\headrulewidth}{0pt}
+    \fancypagestyle{plain}{}
+    ");
+}
+
+#[test]
+fn custom_page_footer() {
+    let defaults = pandoc_defaults(indoc! {r#"
+        [book]
+        authors = ["R&D"]
+
+        [output.pandoc.profile.test]
+        output-file = "/dev/null"
+        to = "latex"
+        page-footer = "Draft by {author} ({date})"
+
+        [output.pandoc.profile.test.metadata]
+        date = "2024-01-01"
+    "#});
+    let header_includes = strings(&defaults["variables"]["header-includes"]);
+    assert!(
+        header_includes.contains(r"\fancyfoot[L]{Draft by R\&D (2024-01-01)}"),
+        "{header_includes}"
+    );
+}
+
+#[test]
+fn profile_titles() {
+    let cfg = indoc! {r#"
+        [book]
+        title = "Book"
+
+        [output.pandoc]
+        keep-preprocessed = false
+
+        [output.pandoc.profile.draft]
+        output-file = "book.md"
+        table-of-contents = false
+        title = "Draft"
+        subtitle = "Work in progress"
+
+        [output.pandoc.profile.final]
+        output-file = "book.md"
+        table-of-contents = false
+        title = "Final"
+    "#};
+    let book = MDBook::options()
+        .max_log_level(tracing::Level::WARN)
+        .init()
+        .mdbook_config(cfg.parse().unwrap())
+        .chapter(Chapter::new("", "# Chapter", "chapter.md"))
+        .build();
+    insta::assert_snapshot!(book, @r"
+    ├─ draft/book.md
+    │ ---
+    │ subtitle: Work in progress
+    │ title: Draft
+    │ ---
+    │ 
+    │ # Chapter {#book__draft__src__chapter.md__chapter}
+    ├─ final/book.md
+    │ ---
+    │ title: Final
+    │ ---
+    │ 
+    │ # Chapter {#book__final__src__chapter.md__chapter}
+    ");
+}
+
+#[test]
+fn title_falls_back_to_first_chapter() {
+    let cfg = indoc! {r#"
+        [output.pandoc.profile.html]
+        output-file = "book.html"
+    "#};
+    let book = MDBook::init()
+        .mdbook_config(cfg.parse().unwrap())
+        .chapter(Chapter::new(
+            "Getting Started",
+            "# Getting Started",
+            "intro.md",
+        ))
+        .chapter(Chapter::new("Usage", "# Usage", "usage.md"))
+        .build();
+    assert!(!book.logs.contains("Book has no title"));
+    let html = std::fs::read_to_string(book.dir.join("html/book.html")).unwrap();
+    assert!(html.contains("<title>Getting Started</title>"));
+}
+
+#[test]
+fn date_format() {
+    let cfg = indoc! {r#"
+        [output.pandoc]
+        date-format = "%B %-d, %Y"
+
+        [output.pandoc.profile.markdown]
+        output-file = "book.md"
+    "#};
+    let book = MDBook::init()
+        .mdbook_config(cfg.parse().unwrap())
+        .chapter(Chapter::new("", "# Chapter", "chapter.md"))
+        .build();
+    let markdown = std::fs::read_to_string(book.dir.join("markdown/book.md")).unwrap();
+    let date = chrono::Local::now().format("%B %-d, %Y");
+    assert!(markdown.contains(&format!("date: {date}\n")));
+
+    let cfg = indoc! {r#"
+        [output.pandoc]
+        date-format = "%Q"
+
+        [output.pandoc.profile.markdown]
+        output-file = "book.md"
+    "#};
+    let book = MDBook::init()
+        .mdbook_config(cfg.parse().unwrap())
+        .chapter(Chapter::new("", "# Chapter", "chapter.md"))
+        .build();
+    assert!(book.logs.contains("Invalid date-format '%Q'"));
+}
+
+#[test]
+fn profile_base() {
+    let cfg = indoc! {r#"
+        [output.pandoc]
+        keep-preprocessed = false
+
+        [output.pandoc.profile.base]
+        output-file = "book.md"
+        table-of-contents = false
+        title = "Base"
+        subtitle = "Shared"
+
+        [output.pandoc.profile.derived]
+        base = "base"
+        title = "Derived"
+    "#};
+    let book = MDBook::options()
+        .max_log_level(tracing::Level::WARN)
+        .init()
+        .mdbook_config(cfg.parse().unwrap())
+        .chapter(Chapter::new("", "# Chapter", "chapter.md"))
+        .build();
+    insta::assert_snapshot!(book, @r"
+    ├─ base/book.md
+    │ ---
+    │ subtitle: Shared
+    │ title: Base
+    │ ---
+    │ 
+    │ # Chapter {#book__base__src__chapter.md__chapter}
+    ├─ derived/book.md
+    │ ---
+    │ subtitle: Shared
+    │ title: Derived
+    │ ---
+    │ 
+    │ # Chapter {#book__derived__src__chapter.md__chapter}
+    ");
+}
+
+#[test]
+fn paragraph_spacing() {
+    let cfg = indoc! {r#"
+        [book]
+        title = "Book"
+
+        [output.pandoc.profile.latex]
+        output-file = "book.tex"
+        paragraph-spacing = "1em plus 2pt"
+    "#};
+    let book = MDBook::init()
+        .mdbook_config(cfg.parse().unwrap())
+        .chapter(Chapter::new(
+            "",
+            "First paragraph.\n\nSecond paragraph.",
+            "chapter.md",
+        ))
+        .build();
+    let latex = std::fs::read_to_string(book.dir.join("latex/book.tex")).unwrap();
+    assert!(latex.contains(r"\setlength{\parskip}{1em plus 2pt}"));
+}
+
+#[test]
+fn openright_with_blank_page_style() {
+    let cfg = indoc! {r#"
+        [book]
+        title = "Book"
+
+        [output.pandoc.profile.latex]
+        output-file = "book.tex"
+        openright = true
+        blank-page-style = "empty"
+        variables.documentclass = "report"
+    "#};
+    let book = MDBook::init()
+        .mdbook_config(cfg.parse().unwrap())
+        .chapter(Chapter::new("One", "# One", "one.md"))
+        .chapter(Chapter::new("Two", "# Two", "two.md"))
         .build();
-    insta::assert_snapshot!(output, @r#"
+    let latex = std::fs::read_to_string(book.dir.join("latex/book.tex")).unwrap();
+    let (class_options, _) = (latex.split_once(r"\documentclass[").unwrap().1)
+        .split_once("]{report}")
+        .unwrap();
+    assert!(class_options.contains("twoside"), "{class_options}");
+    assert!(class_options.contains("openright"), "{class_options}");
+    assert!(latex.contains(r"\renewcommand{\cleardoublepage}"));
+    assert!(latex.contains(r"\thispagestyle{empty}"));
+}
+
+#[test]
+fn unknown_output_format() {
+    let cfg = indoc! {r#"
+        [output.pandoc.profile.latex]
+        output-file = "output.tex"
+        to = "latexx"
+    "#};
+    let book = MDBook::init()
+        .mdbook_config(cfg.parse().unwrap())
+        .chapter(Chapter::new("", "# Chapter", "chapter.md"))
+        .build();
+    assert!(book
+        .logs
+        .contains("unknown output format 'latexx'; did you mean 'latex'?"));
+    assert!(!book.dir.join("latex/output.tex").exists());
+}
+
+#[test]
+fn resources() {
+    let cfg = indoc! {r#"
+        [output.pandoc]
+        keep-preprocessed = false
+
+        [output.pandoc.profile.markdown]
+        output-file = "book.md"
+        standalone = false
+        resources = ["assets/logo.png"]
+    "#};
+    let book = MDBook::init()
+        .mdbook_config(cfg.parse().unwrap())
+        .file_in_root("assets/logo.png", "logo")
+        .chapter(Chapter::new("", "# Chapter", "chapter.md"))
+        .build();
+    insta::assert_snapshot!(book, @r"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/markdown/book.md    
+    ├─ markdown/book.md
+    │ # Chapter {#book__markdown__src__chapter.md__chapter}
+    ├─ markdown/logo.png
+    │ logo
+    ");
+
+    let book = MDBook::init()
+        .mdbook_config(
+            cfg.replace("assets/logo.png", "missing.png")
+                .parse()
+                .unwrap(),
+        )
+        .chapter(Chapter::new("", "# Chapter", "chapter.md"))
+        .build();
+    assert!(book.logs.contains("Resource 'missing.png' does not exist"));
+
+    let book = MDBook::init()
+        .mdbook_config(
+            cfg.replace(
+                r#"["assets/logo.png"]"#,
+                r#"["assets/logo.png", "logo.png"]"#,
+            )
+            .parse()
+            .unwrap(),
+        )
+        .file_in_root("assets/logo.png", "logo")
+        .file_in_root("logo.png", "other logo")
+        .chapter(Chapter::new("", "# Chapter", "chapter.md"))
+        .build();
+    assert!(book
+        .logs
+        .contains("Resources 'assets/logo.png' and 'logo.png' have the same file name"));
+}
+
+#[test]
+fn embed_resources() {
+    let defaults = pandoc_defaults(indoc! {r#"
+        [output.pandoc.profile.test]
+        output-file = "/dev/null"
+        to = "html"
+        embed-resources = true
+        extract-media = "media"
+    "#});
+    assert_eq!(defaults["embed-resources"], true);
+    assert!(defaults.get("extract-media").is_none(), "{defaults:?}");
+}
+
+#[test]
+fn oxford_author_format() {
+    let defaults = pandoc_defaults(indoc! {r#"
+        [book]
+        authors = ["Alice", "Bob", "Carol"]
+
+        [output.pandoc.profile.test]
+        output-file = "/dev/null"
+        to = "markdown"
+        author-format = "oxford"
+    "#});
+    assert_eq!(defaults["metadata"]["author"], "Alice, Bob, and Carol");
+}
+
+#[test]
+fn config_file() {
+    let cfg = indoc! {r#"
+        [output.pandoc]
+        config-file = "pandoc.toml"
+        keep-preprocessed = false
+
+        [output.pandoc.profile.foo]
+        to = "markdown"
+    "#};
+    let external = indoc! {r#"
+        [profile.foo]
+        output-file = "external.md"
+        to = "latex"
+    "#};
+    let book = MDBook::init()
+        .mdbook_config(cfg.parse().unwrap())
+        .file_in_root("pandoc.toml", external)
+        .chapter(Chapter::new("", "# Chapter", "chapter.md"))
+        .build();
+    insta::assert_snapshot!(book, @r"
     ├─ log output
-    │ DEBUG mdbook::book: Running the index preprocessor.    
-    │ DEBUG mdbook::book: Running the links preprocessor.    
     │  INFO mdbook::book: Running the pandoc backend    
-    │ TRACE mdbook_pandoc::pandoc::renderer: Running pandoc with profile: Profile {
-    │     columns: 72,
-    │     file_scope: true,
-    │     number_sections: true,
-    │     output_file: "/dev/null",
-    │     pdf_engine: None,
-    │     standalone: true,
-    │     to: Some(
-    │         "markdown",
-    │     ),
-    │     table_of_contents: true,
-    │     variables: {
-    │         "colorlinks": Boolean(
-    │             false,
-    │         ),
-    │         "dir": String(
-    │             "ltr",
-    │         ),
-    │         "header-includes": Array(
-    │             [
-    │                 String(
-    │                     "text1",
-    │                 ),
-    │                 String(
-    │                     "text2",
-    │                 ),
-    │             ],
-    │         ),
-    │         "indent": Boolean(
-    │             true,
-    │         ),
-    │         "lang": String(
-    │             "en",
-    │         ),
-    │     },
-    │     metadata: {
-    │         "author": Array(
-    │             [
-    │                 String(
-    │                     "John Doe",
-    │                 ),
-    │                 String(
-    │                     "Jane Doe",
-    │                 ),
-    │             ],
-    │         ),
-    │         "description": String(
-    │             "The example book covers examples.",
-    │         ),
-    │         "title": String(
-    │             "Example book",
-    │         ),
-    │     },
-    │     rest: {
-    │         "fail-if-warnings": Boolean(
-    │             false,
-    │         ),
-    │         "resource-path": Array(
-    │             [
-    │                 String(
-    │                     "really-long-path",
-    │                 ),
-    │                 String(
-    │                     "really-long-path2",
-    │                 ),
-    │             ],
-    │         ),
-    │         "verbosity": String(
-    │             "INFO",
-    │         ),
-    │     },
-    │ }    
-    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to /dev/null
-    "#)
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/foo/external.md    
+    ├─ foo/external.md
+    │ # Chapter {#book__foo__src__chapter.md__chapter}
+    ");
+}
+
+#[test]
+fn code_block_backend() {
+    let cfg = indoc! {r#"
+        [book]
+        title = "Book"
+
+        [output.pandoc.code]
+        style-diffs = true
+
+        [output.pandoc.profile.latex]
+        output-file = "book.tex"
+        code-block-backend = "BACKEND"
+    "#};
+    let content = "```rust\nfn main() {}\n```\n\n```diff\n+added\n```";
+    let build = |backend: &str| {
+        MDBook::init()
+            .mdbook_config(cfg.replace("BACKEND", backend).parse().unwrap())
+            .chapter(Chapter::new("", content, "chapter.md"))
+            .build()
+    };
+    let read = |book: &super::BuildOutput| {
+        std::fs::read_to_string(book.dir.join("latex/book.tex")).unwrap()
+    };
+
+    let latex = read(&build("fvextra"));
+    assert!(latex.contains(r"\usepackage{fvextra}"), "{latex}");
+    assert!(latex.contains(r"\begin{Highlighting}"), "{latex}");
+
+    let latex = read(&build("listings"));
+    assert!(!latex.contains("fvextra"), "{latex}");
+    assert!(latex.contains(r"\lstset{basicstyle=\ttfamily, columns=fullflexible, breaklines=true}"));
+    assert!(latex.contains(r"\begin{lstlisting}"), "{latex}");
+    // Styled diffs need `listings` too, which is only loaded once
+    assert_eq!(
+        latex.matches(r"\usepackage{listings}").count(),
+        1,
+        "{latex}"
+    );
+
+    let book = build("minted");
+    assert!(book
+        .logs
+        .contains("requires running LaTeX with `-shell-escape`"));
+    let latex = read(&book);
+    assert!(!latex.contains("fvextra"), "{latex}");
+    assert!(latex.contains(r"\usepackage{minted}"), "{latex}");
+    assert!(
+        latex.contains("\\begin{minted}[breaklines]{rust}\nfn main() {}\n\\end{minted}"),
+        "{latex}"
+    );
+}
+
+#[test]
+fn description_as_abstract() {
+    let cfg = indoc! {r#"
+        [book]
+        title = "Book"
+        description = "A book about *things* & stuff."
+
+        [output.pandoc.profile.latex]
+        output-file = "book.tex"
+        description-as-abstract = true
+    "#};
+    let book = MDBook::init()
+        .mdbook_config(cfg.parse().unwrap())
+        .chapter(Chapter::new("", "# Chapter", "chapter.md"))
+        .build();
+    let latex = std::fs::read_to_string(book.dir.join("latex/book.tex")).unwrap();
+    let (_, abstract_) = latex.split_once(r"\begin{abstract}").unwrap();
+    let (abstract_, _) = abstract_.split_once(r"\end{abstract}").unwrap();
+    assert!(abstract_.contains(r"A book about"), "{abstract_}");
+    assert!(abstract_.contains(r"\&"), "{abstract_}");
+}
+
+#[test]
+fn url_style() {
+    let cfg = indoc! {r#"
+        [book]
+        title = "Book"
+
+        [output.pandoc.profile.latex]
+        output-file = "book.tex"
+        url-style = "STYLE"
+    "#};
+    let long_url = "https://example.com/a/very/long/path/that/would/overflow/the/margins/of/a/page";
+    let build = |style: &str| {
+        let book = MDBook::init()
+            .mdbook_config(cfg.replace("STYLE", style).parse().unwrap())
+            .chapter(Chapter::new(
+                "",
+                format!("See [the docs]({long_url}) or <{long_url}>."),
+                "chapter.md",
+            ))
+            .build();
+        std::fs::read_to_string(book.dir.join("latex/book.tex")).unwrap()
+    };
+
+    let latex = build("inline");
+    assert!(
+        latex.contains(&format!(
+            r"\href{{{long_url}}}{{the docs}} or \url{{{long_url}}}"
+        )),
+        "{latex}"
+    );
+    assert!(!latex.contains(r"\footnote"), "{latex}");
+    assert!(!latex.contains("xurl"), "{latex}");
+
+    let latex = build("footnote");
+    assert!(
+        latex.contains(&format!(
+            r"\href{{{long_url}}}{{the docs}}\footnote{{\url{{{long_url}}}}} or \url{{{long_url}}}"
+        )),
+        "{latex}"
+    );
+
+    let latex = build("break");
+    assert!(latex.contains(r"\usepackage{xurl}"), "{latex}");
+    assert!(!latex.contains(r"\footnote"), "{latex}");
+}
+
+#[test]
+fn output_to_stdout() {
+    let cfg = indoc! {r#"
+        [book]
+        title = "Book"
+
+        [output.pandoc.profile.piped]
+        output-file = "-"
+        to = "markdown"
+    "#};
+    let book = MDBook::init()
+        .mdbook_config(cfg.parse().unwrap())
+        .chapter(Chapter::new("", "# Chapter", "chapter.md"))
+        .build();
+    assert!(
+        book.logs.contains("Wrote output to stdout"),
+        "{}",
+        book.logs
+    );
+    assert!(!book.dir.join("piped/-").exists());
+}
+
+#[test]
+fn format_specific_variables() {
+    let profile = indoc! {r#"
+        output-file = "OUTPUT"
+
+        [variables]
+        lang = "en"
+
+        [variables.latex]
+        lang = "de"
+    "#};
+    let build = |output_file: &str| {
+        let cfg = format!(
+            "[book]\ntitle = \"Book\"\n\n[output.pandoc.profile.book]\n{}",
+            profile.replace("OUTPUT", output_file)
+        )
+        .replace("[variables", "[output.pandoc.profile.book.variables");
+        let book = MDBook::init()
+            .mdbook_config(cfg.parse().unwrap())
+            .chapter(Chapter::new("", "# Chapter", "chapter.md"))
+            .build();
+        std::fs::read_to_string(book.dir.join("book").join(output_file)).unwrap()
+    };
+
+    let latex = build("book.tex");
+    assert!(latex.contains("pdflang={de}"), "{latex}");
+    assert!(!latex.contains("pdflang={en}"), "{latex}");
+
+    let html = build("book.html");
+    assert!(html.contains(r#"lang="en""#), "{html}");
+    assert!(!html.contains(r#"lang="de""#), "{html}");
+}
+
+#[test]
+fn dpi() {
+    let cfg = |dpi: u32| {
+        format!(
+            "[output.pandoc]\ndump-command = true\n\n\
+            [output.pandoc.profile.test]\noutput-file = \"output.md\"\ndpi = {dpi}"
+        )
+    };
+    let book = MDBook::init()
+        .mdbook_config(cfg(300).parse().unwrap())
+        .chapter(Chapter::new("", "# Chapter", "chapter.md"))
+        .build();
+    let defaults = std::fs::read_to_string(book.dir.join("test/pandoc-defaults.yaml")).unwrap();
+    assert!(defaults.contains("dpi: 300"), "{defaults}");
+
+    let book = MDBook::init()
+        .mdbook_config(cfg(0).parse().unwrap())
+        .chapter(Chapter::new("", "# Chapter", "chapter.md"))
+        .build();
+    assert!(book.logs.contains("nonzero"), "{}", book.logs);
+}
+
+/// Builds a book with configuration `cfg`, returning the defaults file Pandoc is run with for the
+/// profile named `test`.
+fn pandoc_defaults(cfg: &str) -> serde_yaml::Value {
+    let cfg = format!("[output.pandoc]\ndump-command = true\n\n{cfg}");
+    let book = MDBook::init().mdbook_config(cfg.parse().unwrap()).build();
+    let defaults = std::fs::read_to_string(book.dir.join("test/pandoc-defaults.yaml"))
+        .unwrap_or_else(|err| panic!("{err}\n{}", book.logs));
+    serde_yaml::from_str(&defaults).unwrap()
+}
+
+/// Joins the non-empty strings in an array-valued defaults entry with blank lines.
+fn strings(value: &serde_yaml::Value) -> String {
+    (value.as_sequence().unwrap().iter())
+        .map(|value| value.as_str().unwrap().trim())
+        .filter(|value| !value.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
 }
@@ -35,9 +35,38 @@ fn css() {
     │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
     │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
     ├─ latex/output.tex
+    │ \phantomsection\label{book__latex__src__chapter.md__mdbook-pandoc-chapter-start}
+    │ 
     │ \includegraphics[width=1.04167in,height=0.52083in]{book/latex/src/img/image.png}
     ├─ latex/src/chapter.md
-    │ [Plain [Image ("", ["ferris-explain"], [("height", "50"), ("width", "100px")]) [Str "alt text"] ("book/latex/src/img/image.png", "a title")]]
+    │ [Div ("mdbook-pandoc-chapter-start", [], []) [], Plain [Image ("", ["ferris-explain"], [("height", "50"), ("width", "100px")]) [Str "alt text"] ("book/latex/src/img/image.png", "a title")]]
     ├─ latex/src/img/image.png
     "#);
 }
+
+#[test]
+fn extra_css() {
+    let cfg = indoc! {r#"
+        [output.html]
+        additional-css = ["ferris.css"]
+
+        [output.pandoc.html]
+        extra-css = ["overrides.css", "missing.css"]
+
+        [output.pandoc.profile.html]
+        output-file = "book.html"
+    "#};
+    let book = MDBook::init()
+        .mdbook_config(mdbook::Config::from_str(cfg).unwrap())
+        .file_in_root("ferris.css", "")
+        .file_in_root("overrides.css", "")
+        .chapter(Chapter::new("", "# Chapter", "chapter.md"))
+        .build();
+    assert!(book
+        .logs
+        .contains("CSS stylesheet 'missing.css' does not exist"));
+    let html = std::fs::read_to_string(book.dir.join("html/book.html")).unwrap();
+    let ferris = html.find(r#"href="ferris.css""#).unwrap();
+    let overrides = html.find(r#"href="overrides.css""#).unwrap();
+    assert!(ferris < overrides);
+}
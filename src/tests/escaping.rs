@@ -24,3 +24,23 @@ fn preserve_escapes() {
     │ ]
     "#);
 }
+
+#[test]
+fn latex_math_macros_in_html() {
+    let output = MDBook::init()
+        .config(Config::html())
+        .chapter(Chapter::new(
+            "",
+            r"$\newcommand{\R}{\mathbb{R}} x \in \R$",
+            "chapter.md",
+        ))
+        .build();
+    insta::assert_snapshot!(output, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/html/book.html    
+    ├─ html/book.html
+    │ <p>$\newcommand{\R}{\mathbb{R}} x \in \R$</p>
+    "#);
+}
@@ -1,3 +1,7 @@
+use indoc::indoc;
+
+use crate::{ChapterHeading, ChapterHeadingText, ChapterHeadings, HeadingIds};
+
 use super::{Chapter, Config, MDBook};
 
 #[test]
@@ -24,6 +28,30 @@ fn heading_attributes() {
     "##);
 }
 
+#[test]
+fn duplicate_explicit_heading_ids() {
+    let book = MDBook::init()
+        .chapter(Chapter::new("One", "# One {#intro}", "one.md"))
+        .chapter(Chapter::new("Two", "# Two {#intro}", "two.md"))
+        .config(Config::latex())
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  WARN mdbook_pandoc::preprocess: Heading id 'intro' in chapter 'Two' is already used in chapter 'One'    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ \chapter{One}\label{book__latex__src__one.md__intro}
+    │ 
+    │ \chapter{Two}\label{book__latex__src__two.md__intro}
+    ├─ latex/src/one.md
+    │ [Header 1 ("intro", [], []) [Str "One"]]
+    ├─ latex/src/two.md
+    │ [Header 1 ("intro", [], []) [Str "Two"]]
+    "#);
+}
+
 #[test]
 fn nested_chapters() {
     let book = MDBook::init()
@@ -139,6 +167,59 @@ fn repeated_identifiers() {
     "##);
 }
 
+#[test]
+fn heading_id_algorithms() {
+    let chapter = Chapter::new(
+        "",
+        "# 3. Applications\n## Hello, world.\n## Hello, world.\n## 33",
+        "chapter.md",
+    );
+    let book = MDBook::init()
+        .chapter(chapter.clone())
+        .config(Config::latex())
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ \chapter{3. Applications}\label{book__latex__src__chapter.md__3-applications}
+    │ 
+    │ \section*{Hello, world.}\label{book__latex__src__chapter.md__hello-world}
+    │ 
+    │ \section*{Hello, world.}\label{book__latex__src__chapter.md__hello-world-1}
+    │ 
+    │ \section*{33}\label{book__latex__src__chapter.md__33}
+    ├─ latex/src/chapter.md
+    │ [Header 1 ("3-applications", [], []) [Str "3. Applications"], Header 2 ("hello-world", ["unnumbered", "unlisted"], []) [Str "Hello, world."], Header 2 ("hello-world-1", ["unnumbered", "unlisted"], []) [Str "Hello, world."], Header 2 ("33", ["unnumbered", "unlisted"], []) [Str "33"]]
+    "#);
+
+    let book = MDBook::init()
+        .chapter(chapter)
+        .config(Config {
+            heading_ids: HeadingIds::Pandoc,
+            ..Config::latex()
+        })
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ \chapter{3. Applications}\label{book__latex__src__chapter.md__applications}
+    │ 
+    │ \section*{Hello, world.}\label{book__latex__src__chapter.md__hello-world.}
+    │ 
+    │ \section*{Hello, world.}\label{book__latex__src__chapter.md__hello-world.-1}
+    │ 
+    │ \section*{33}\label{book__latex__src__chapter.md__section}
+    ├─ latex/src/chapter.md
+    │ [Header 1 ("applications", [], []) [Str "3. Applications"], Header 2 ("hello-world.", ["unnumbered", "unlisted"], []) [Str "Hello, world."], Header 2 ("hello-world.-1", ["unnumbered", "unlisted"], []) [Str "Hello, world."], Header 2 ("section", ["unnumbered", "unlisted"], []) [Str "33"]]
+    "#);
+}
+
 #[test]
 fn parts() {
     let book = MDBook::init()
@@ -166,3 +247,273 @@ fn parts() {
     │ [Header 1 ("two", [], []) [Str "Two"]]
     "#);
 }
+
+#[test]
+fn parts_with_top_level_division() {
+    let cfg = indoc! {r#"
+        [output.pandoc.profile.latex]
+        output-file = "output.tex"
+        standalone = false
+        top-level-division = "part"
+
+        [output.pandoc.profile.latex.variables]
+        documentclass = "report"
+    "#};
+    let book = MDBook::init()
+        .mdbook_config(cfg.parse().unwrap())
+        .chapter(Chapter::new("", "# One", "one.md"))
+        .part("part two")
+        .chapter(Chapter::new("", "# Two", "two.md"))
+        .build();
+    let output = std::fs::read_to_string(book.dir.join("latex/output.tex")).unwrap();
+    assert_eq!(output.matches(r"\part{").count(), 1, "{output}");
+    assert!(output.contains(r"\chapter{One}"), "{output}");
+    assert!(output.contains(r"\chapter{Two}"), "{output}");
+    assert!(
+        book.logs
+            .contains("Ignoring `top-level-division = \"part\"`"),
+        "{}",
+        book.logs
+    );
+}
+
+#[test]
+fn book_class_matter_parts() {
+    let mut cfg = Config::latex();
+    let profile = cfg.profiles.get_mut("latex").unwrap();
+    profile
+        .variables
+        .insert("documentclass".into(), "book".into());
+    let book = MDBook::init()
+        .part("Front Matter")
+        .chapter(Chapter::new("", "# Preface", "preface.md"))
+        .part("Part One")
+        .chapter(Chapter::new("", "# One", "one.md"))
+        .part("Back Matter")
+        .chapter(Chapter::new("", "# Appendix", "appendix.md"))
+        .config(cfg)
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ \frontmatter
+    │ 
+    │ \chapter{Preface}\label{book__latex__src__preface.md__preface}
+    │ 
+    │ \mainmatter
+    │ 
+    │ \part{Part One}
+    │ 
+    │ \chapter{One}\label{book__latex__src__one.md__one}
+    │ 
+    │ \backmatter
+    │ 
+    │ \chapter{Appendix}\label{book__latex__src__appendix.md__appendix}
+    ├─ latex/src/appendix.md
+    │ [Header 1 ("appendix", [], []) [Str "Appendix"]]
+    ├─ latex/src/one.md
+    │ [Header 1 ("one", [], []) [Str "One"]]
+    ├─ latex/src/part-1-front-matter.md
+    │ [RawBlock (Format "latex") "\\frontmatter"]
+    ├─ latex/src/part-2-part-one.md
+    │ [RawBlock (Format "latex") "\\mainmatter", Para [RawInline (Format "latex") "\\part{Part One}"]]
+    ├─ latex/src/part-3-back-matter.md
+    │ [RawBlock (Format "latex") "\\backmatter"]
+    ├─ latex/src/preface.md
+    │ [Header 1 ("preface", [], []) [Str "Preface"]]
+    "#);
+}
+
+#[test]
+fn page_break_before_headings() {
+    let mut cfg = Config::latex();
+    // Chapters already start on new pages, so level 1 shouldn't add another break
+    cfg.profiles.get_mut("latex").unwrap().page_break_before = vec![1, 2];
+    let book = MDBook::init()
+        .chapter(Chapter::new("", "# One\n## Two\n## Three", "chapter.md"))
+        .config(cfg)
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ \chapter{One}\label{book__latex__src__chapter.md__one}
+    │ 
+    │ \clearpage
+    │ 
+    │ \section*{Two}\label{book__latex__src__chapter.md__two}
+    │ 
+    │ \clearpage
+    │ 
+    │ \section*{Three}\label{book__latex__src__chapter.md__three}
+    ├─ latex/src/chapter.md
+    │ [Header 1 ("one", [], []) [Str "One"], RawBlock (Format "latex") "\\clearpage", Header 2 ("two", ["unnumbered", "unlisted"], []) [Str "Two"], RawBlock (Format "latex") "\\clearpage", Header 2 ("three", ["unnumbered", "unlisted"], []) [Str "Three"]]
+    "#);
+}
+
+#[test]
+fn nested_chapters_without_shrinking_headings() {
+    let book = MDBook::init()
+        .chapter(Chapter::new("One", "# One", "one.md").child(Chapter::new(
+            "One.One",
+            "# Top\n## Another",
+            "onepointone.md",
+        )))
+        .config(Config {
+            shrink_nested_headings: false,
+            ..Config::latex()
+        })
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ \chapter{One}\label{book__latex__src__one.md__one}
+    │ 
+    │ \chapter{Top}\label{book__latex__src__onepointone.md__top}
+    │ 
+    │ \section*{Another}\label{book__latex__src__onepointone.md__another}
+    ├─ latex/src/one.md
+    │ [Header 1 ("one", [], []) [Str "One"]]
+    ├─ latex/src/onepointone.md
+    │ [Header 1 ("top", [], []) [Str "Top"], Header 2 ("another", ["unnumbered", "unlisted"], []) [Str "Another"]]
+    "#);
+}
+
+#[test]
+fn line_break_in_heading() {
+    let book = MDBook::init()
+        .chapter(Chapter::new(
+            "One",
+            "# Line1<br>Line2\n[link](#line1line2)",
+            "one.md",
+        ))
+        .chapter(Chapter::new("Two", "# Two\n[one](one.md)", "two.md"))
+        .config(Config::latex())
+        .build();
+    insta::assert_snapshot!(book, @r##"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ \chapter{\texorpdfstring{Line1\\
+    │ Line2}{Line1 Line2}}\label{book__latex__src__one.md__line1line2}
+    │ 
+    │ \hyperref[book__latex__src__one.md__line1line2]{link}
+    │ 
+    │ \chapter{Two}\label{book__latex__src__two.md__two}
+    │ 
+    │ \hyperref[book__latex__src__one.md__line1line2]{one}
+    ├─ latex/src/one.md
+    │ [Header 1 ("line1line2", [], []) [Str "Line1", LineBreak, Str "Line2"], Para [Link ("", [], []) [Str "link"] ("#line1line2", "")]]
+    ├─ latex/src/two.md
+    │ [Header 1 ("two", [], []) [Str "Two"], Para [Link ("", [], []) [Str "one"] ("book/latex/src/one.md#line1line2", "")]]
+    "##);
+}
+
+#[test]
+fn without_heading_classes() {
+    let book = MDBook::init()
+        .config(Config {
+            emit_heading_classes: false,
+            ..Config::pandoc()
+        })
+        .chapter(Chapter::new("", "# Hello\n## Subheading", "chapter.md"))
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/markdown/pandoc-ir    
+    ├─ markdown/pandoc-ir
+    │ [ Header
+    │     1
+    │     ( "book__markdown__src__chapter.md__hello" , [] , [] )
+    │     [ Str "Hello" ]
+    │ , Header
+    │     2
+    │     ( "book__markdown__src__chapter.md__subheading" , [] , [] )
+    │     [ Str "Subheading" ]
+    │ ]
+    "#);
+}
+
+#[test]
+fn unnumbered_chapter_headings() {
+    let build = |chapter_headings| {
+        MDBook::init()
+            .unnumbered_chapter(Chapter::new("", "# Preface", "preface.md"))
+            .chapter(Chapter::new("", "# One", "one.md"))
+            .config(Config {
+                chapter_headings,
+                ..Config::latex()
+            })
+            .build()
+    };
+    insta::assert_snapshot!(build(ChapterHeadings::default()), @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ \chapter*{Preface}\label{book__latex__src__preface.md__preface}
+    │ \addcontentsline{toc}{chapter}{Preface}
+    │ 
+    │ \chapter{One}\label{book__latex__src__one.md__one}
+    ├─ latex/src/one.md
+    │ [Header 1 ("one", [], []) [Str "One"]]
+    ├─ latex/src/preface.md
+    │ [Header 1 ("preface", ["unnumbered"], []) [Str "Preface"]]
+    "#);
+    let chapter_headings = ChapterHeadings {
+        numbered_chapters: ChapterHeading::Unnumbered,
+        unnumbered_chapters: ChapterHeading::Unlisted,
+        ..Default::default()
+    };
+    insta::assert_snapshot!(build(chapter_headings), @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ \chapter*{Preface}\label{book__latex__src__preface.md__preface}
+    │ 
+    │ \chapter*{One}\label{book__latex__src__one.md__one}
+    │ \addcontentsline{toc}{chapter}{One}
+    ├─ latex/src/one.md
+    │ [Header 1 ("one", ["unnumbered"], []) [Str "One"]]
+    ├─ latex/src/preface.md
+    │ [Header 1 ("preface", ["unnumbered", "unlisted"], []) [Str "Preface"]]
+    "#);
+}
+
+#[test]
+fn chapter_heading_text() {
+    let build = |text| {
+        let book = MDBook::init()
+            .chapter(Chapter::new(
+                "Getting Started",
+                "# Introduction\n\n## Details",
+                "chapter.md",
+            ))
+            .config(Config {
+                chapter_headings: ChapterHeadings {
+                    text,
+                    ..Default::default()
+                },
+                ..Config::latex()
+            })
+            .build();
+        std::fs::read_to_string(book.dir.join("latex/src/chapter.md")).unwrap()
+    };
+    insta::assert_snapshot!(build(ChapterHeadingText::Heading), @r#"[Header 1 ("introduction", [], []) [Str "Introduction"], Header 2 ("details", [], []) [Str "Details"]]"#);
+    insta::assert_snapshot!(build(ChapterHeadingText::Summary), @r#"[Header 1 ("introduction", [], []) [Str "Getting Started"], Header 2 ("details", [], []) [Str "Details"]]"#);
+}
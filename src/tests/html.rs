@@ -308,10 +308,12 @@ fn attach_id_to_div_of_stripped_html_elements() {
     │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
     │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
     ├─ latex/output.tex
+    │ \phantomsection\label{book__latex__src__chapter.md__mdbook-pandoc-chapter-start}
+    │ 
     │ \phantomsection\label{book__latex__src__chapter.md__foo=bar}
     │ \hyperref[book__latex__src__chapter.md__foo=bar]{}something here
     ├─ latex/src/chapter.md
-    │ [RawBlock (Format "html") "<dt id=\"foo=bar\">", Div ("foo=bar", [], []) [Plain [Link ("", [], [("href", "#foo=bar")]) [] ("#foo=bar", ""), Str "something here"]], RawBlock (Format "html") "</dt>"]
+    │ [Div ("mdbook-pandoc-chapter-start", [], []) [], RawBlock (Format "html") "<dt id=\"foo=bar\">", Div ("foo=bar", [], []) [Plain [Link ("", [], [("href", "#foo=bar")]) [] ("#foo=bar", ""), Str "something here"]], RawBlock (Format "html") "</dt>"]
     "##);
 }
 
@@ -344,3 +346,436 @@ fn noscript_element() {
     │ ]
     "#);
 }
+
+#[test]
+fn data_attributes_in_html() {
+    let book = MDBook::init()
+        .config(Config {
+            keep_preprocessed: true,
+            ..Config::html()
+        })
+        .chapter(Chapter::new(
+            "",
+            r#"Some <span class="hook" data-foo="bar">text</span>."#,
+            "chapter.md",
+        ))
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/html/book.html    
+    ├─ html/book.html
+    │ <p>Some <span class="hook" data-foo="bar">text</span>.</p>
+    ├─ html/src/chapter.md
+    │ [Para [Str "Some ", Span ("", ["hook"], [("data-foo", "bar")]) [Str "text"], Str "."]]
+    "#);
+}
+
+#[test]
+fn keyboard_shortcuts() {
+    let book = MDBook::init()
+        .config(Config {
+            keyboard_shortcuts: true,
+            ..Config::latex()
+        })
+        .chapter(Chapter::new(
+            "",
+            "Press <kbd>Ctrl</kbd>+<kbd>Shift</kbd>+<kbd>C</kbd> to copy, or <kbd>Esc</kbd> to cancel.",
+            "chapter.md",
+        ))
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ Press \keys{Ctrl + Shift + C} to copy, or \keys{Esc} to cancel.
+    ├─ latex/src/chapter.md
+    │ [Para [Str "Press ", RawInline (Format "latex") "\\keys{Ctrl + Shift + C}", Str " to copy, or ", RawInline (Format "latex") "\\keys{Esc}", Str " to cancel."]]
+    "#);
+}
+
+#[test]
+fn sidenotes() {
+    let book = MDBook::init()
+        .config(Config {
+            sidenotes: true,
+            ..Config::latex()
+        })
+        .chapter(Chapter::new(
+            "",
+            r#"A claim.<span class="sidenote">With a source.</span> More text."#,
+            "chapter.md",
+        ))
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ A claim.\sidenote{With a source.} More text.
+    ├─ latex/src/chapter.md
+    │ [Para [Str "A claim.", RawInline (Format "latex") "\\sidenote{", Str "With a source.", RawInline (Format "latex") "}", Str " More text."]]
+    "#);
+}
+
+#[test]
+fn flatten_tabs() {
+    let cfg = indoc! {r#"
+        [book]
+        title = "Book"
+
+        [output.pandoc.html]
+        flatten-tabs = "tabbed"
+
+        [output.pandoc.profile.latex]
+        output-file = "book.tex"
+    "#};
+    let book = MDBook::init()
+        .mdbook_config(cfg.parse().unwrap())
+        .chapter(Chapter::new(
+            "",
+            indoc! {r#"
+                # Installation
+
+                <div class="tabbed">
+                <div class="tab-buttons"><button>Linux</button><button>macOS</button></div>
+                <div>
+
+                Run `apt install foo`.
+
+                </div>
+                <div>
+
+                Run `brew install foo`.
+
+                </div>
+                </div>
+            "#},
+            "chapter.md",
+        ))
+        .build();
+    let latex = std::fs::read_to_string(book.dir.join("latex/book.tex")).unwrap();
+    assert_eq!(latex.matches("section*{").count(), 2, "{latex}");
+    let linux = latex.find("section*{Linux}").unwrap();
+    let macos = latex.find("section*{macOS}").unwrap();
+    let apt = latex.find("apt install foo").unwrap();
+    let brew = latex.find("brew install foo").unwrap();
+    assert!(linux < apt && apt < macos && macos < brew, "{latex}");
+}
+
+#[test]
+fn fold_sections() {
+    let cfg = indoc! {r#"
+        [output.pandoc.html]
+        fold-level = 2
+
+        [output.pandoc.profile.html]
+        output-file = "book.html"
+        standalone = false
+    "#};
+    let content = indoc! {"
+        # Chapter
+
+        Introduction.
+
+        ## One
+
+        First.
+
+        ### Nested
+
+        Nested.
+
+        ## Two
+
+        Second.
+    "};
+    let book = MDBook::init()
+        .mdbook_config(cfg.parse().unwrap())
+        .chapter(Chapter::new("", content, "chapter.md"))
+        .build();
+    let html = std::fs::read_to_string(book.dir.join("html/book.html")).unwrap();
+    assert_eq!(html.matches("<details>").count(), 3, "{html}");
+    assert_eq!(html.matches("</details>").count(), 3, "{html}");
+    // The chapter heading and introduction stay outside the folded sections
+    assert!(html.find("Introduction.").unwrap() < html.find("<details>").unwrap());
+    let positions = [
+        "<summary>",
+        "One",
+        "</summary>",
+        "First.",
+        "<summary>",
+        "Nested",
+    ]
+    .into_iter()
+    .scan(0, |start, text| {
+        let pos = *start + html[*start..].find(text)?;
+        *start = pos + text.len();
+        Some(pos)
+    })
+    .count();
+    assert_eq!(positions, 6, "{html}");
+    // Both H2 sections are closed before the next begins
+    let two = html.find("Two").unwrap();
+    assert_eq!(html[..two].matches("</details>").count(), 2, "{html}");
+
+    // Other formats are unaffected
+    let cfg = cfg.replace(
+        "[output.pandoc.profile.html]\noutput-file = \"book.html\"",
+        "[output.pandoc.profile.latex]\noutput-file = \"book.tex\"",
+    );
+    let book = MDBook::init()
+        .mdbook_config(cfg.parse().unwrap())
+        .chapter(Chapter::new("", content, "chapter.md"))
+        .build();
+    let latex = std::fs::read_to_string(book.dir.join("latex/book.tex")).unwrap();
+    assert!(!latex.contains("details"), "{latex}");
+}
+
+#[test]
+fn trim_html_whitespace() {
+    let ast = MDBook::init()
+        .chapter(Chapter::new(
+            "Chapter",
+            indoc! {"
+                <details>
+                <summary>
+
+                ## Heading
+
+                text
+
+                </summary>
+
+                more **markdown**
+
+                </details>
+
+                outside <b>bold</b> <i>italic</i>
+            "},
+            "chapter.md",
+        ))
+        .config(Config {
+            trim_html_whitespace: true,
+            ..Config::pandoc()
+        })
+        .build();
+    insta::assert_snapshot!(ast, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/markdown/pandoc-ir    
+    ├─ markdown/pandoc-ir
+    │ [ RawBlock (Format "html") "<details>"
+    │ , Div
+    │     ( "" , [] , [] )
+    │     [ Plain [ Str "\n" , RawInline (Format "html") "<summary>" ]
+    │     , Div
+    │         ( "" , [] , [] )
+    │         [ Header
+    │             2
+    │             ( "book__markdown__src__chapter.md__heading"
+    │             , [ "unnumbered" , "unlisted" ]
+    │             , []
+    │             )
+    │             [ Str "Heading" ]
+    │         , Para [ Str "text" ]
+    │         ]
+    │     , RawBlock (Format "html") "</summary>"
+    │     , Para [ Str "more " , Strong [ Str "markdown" ] ]
+    │     ]
+    │ , RawBlock (Format "html") "</details>"
+    │ , Para
+    │     [ Str "outside "
+    │     , RawInline (Format "html") "<b>"
+    │     , Span ( "" , [] , [] ) [ Str "bold" ]
+    │     , RawInline (Format "html") "</b>"
+    │     , Str " "
+    │     , RawInline (Format "html") "<i>"
+    │     , Span ( "" , [] , [] ) [ Str "italic" ]
+    │     , RawInline (Format "html") "</i>"
+    │     ]
+    │ ]
+    "#);
+}
+
+#[test]
+fn strip_interactive() {
+    let cfg = indoc! {r#"
+        [book]
+        title = "Book"
+
+        [output.pandoc.html]
+        strip-interactive = [".quiz", "quiz-widget"]
+
+        [output.pandoc.profile.latex]
+        output-file = "book.tex"
+
+        [output.pandoc.profile.html]
+        output-file = "book.html"
+        standalone = false
+    "#};
+    let content = indoc! {r#"
+        Before the quiz.
+
+        <div class="quiz" data-quiz="quiz.toml">What is 1 + 1?</div>
+
+        <quiz-widget>Pick one</quiz-widget>
+
+        After the quiz.
+    "#};
+    let book = MDBook::init()
+        .mdbook_config(cfg.parse().unwrap())
+        .chapter(Chapter::new("", content, "chapter.md"))
+        .build();
+    let latex = std::fs::read_to_string(book.dir.join("latex/book.tex")).unwrap();
+    assert!(latex.contains("Before the quiz."), "{latex}");
+    assert!(latex.contains("After the quiz."), "{latex}");
+    assert!(!latex.contains("What is 1 + 1?"), "{latex}");
+    assert!(!latex.contains("Pick one"), "{latex}");
+    let html = std::fs::read_to_string(book.dir.join("html/book.html")).unwrap();
+    assert!(
+        html.contains(r#"<div class="quiz" data-quiz="quiz.toml">"#),
+        "{html}"
+    );
+    assert!(html.contains("What is 1 + 1?"), "{html}");
+    assert!(html.contains("Pick one"), "{html}");
+}
+
+#[test]
+fn admonition_role() {
+    let content = indoc! {r#"
+        <div class="admonition note">
+
+        Remember this.
+
+        </div>
+
+        <div class="admonition tip" role="doc-tip" aria-label="Tip">
+
+        Try this.
+
+        </div>
+    "#};
+    let book = MDBook::init()
+        .config(Config::html())
+        .chapter(Chapter::new("", content, "chapter.md"))
+        .build();
+    let html = std::fs::read_to_string(book.dir.join("html/book.html")).unwrap();
+    assert!(
+        html.contains(r#"<div class="admonition note" role="note">"#),
+        "{html}"
+    );
+    assert!(
+        html.contains(r#"<div class="admonition tip" role="doc-tip" aria-label="Tip">"#),
+        "{html}"
+    );
+}
+
+#[test]
+fn details_open() {
+    let content = indoc! {r#"
+        <details open>
+        <summary>Expanded</summary>
+
+        Shown by default.
+
+        </details>
+
+        <details>
+        <summary>Collapsed</summary>
+
+        Hidden by default.
+
+        </details>
+    "#};
+    let book = MDBook::init()
+        .config(Config::html())
+        .chapter(Chapter::new("", content, "chapter.md"))
+        .build();
+    let html = std::fs::read_to_string(book.dir.join("html/book.html")).unwrap();
+    assert_eq!(html.matches(r#"<details open="">"#).count(), 1, "{html}");
+    assert_eq!(html.matches("<details>").count(), 1, "{html}");
+}
+
+#[test]
+fn annotate_chapters() {
+    let cfg = indoc! {r#"
+        [output.pandoc.html]
+        annotate-chapters = true
+
+        [output.pandoc.profile.html]
+        output-file = "book.html"
+        standalone = false
+    "#};
+    let book = MDBook::init()
+        .mdbook_config(cfg.parse().unwrap())
+        .chapter(Chapter::new("One", "# One", "one.md"))
+        .chapter(Chapter::new("Two", "# Two", "nested/two.md"))
+        .build();
+    let html = std::fs::read_to_string(book.dir.join("html/book.html")).unwrap();
+    let one = html.find("<!-- chapter: one.md -->").expect(&html);
+    let two = html.find("<!-- chapter: nested/two.md -->").expect(&html);
+    assert!(one < html.find("One").unwrap() && one < two, "{html}");
+}
+
+#[test]
+fn orphaned_elements() {
+    let content = indoc! {r#"
+        A <td>stray cell</td> in a paragraph.
+
+        <dd>A definition before any term</dd>
+        <dt>A term</dt>
+    "#};
+    let book = MDBook::init()
+        .config(Config::latex())
+        .chapter(Chapter::new("", content, "chapter.md"))
+        .build();
+    assert!(!book.logs.contains("ERROR"), "{}", book.logs);
+    assert!(
+        book.logs
+            .contains("Found <dd> outside of <dl> in chapter ''"),
+        "{}",
+        book.logs
+    );
+    let latex = std::fs::read_to_string(book.dir.join("latex/output.tex")).unwrap();
+    for text in ["stray cell", "A definition before any term", "A term"] {
+        assert!(latex.contains(text), "{latex}");
+    }
+}
+
+#[test]
+fn block_elements() {
+    let content = indoc! {r#"
+        <custom-callout>
+
+        Some **markdown**.
+
+        </custom-callout>
+    "#};
+    let build = |block_elements: &str| {
+        let cfg = format!(
+            "[output.pandoc.html]\nblock-elements = [{block_elements}]\n\n\
+            [output.pandoc.profile.latex]\noutput-file = \"output.tex\"\nstandalone = false"
+        );
+        let book = MDBook::init()
+            .mdbook_config(cfg.parse().unwrap())
+            .chapter(Chapter::new("", content, "chapter.md"))
+            .build();
+        std::fs::read_to_string(book.dir.join("latex/src/chapter.md")).unwrap()
+    };
+
+    let native = build("");
+    assert!(!native.contains("Div"), "{native}");
+
+    let native = build(r#""custom-callout""#);
+    assert!(
+        native.contains(r#"RawBlock (Format "html") "<custom-callout>", Div ("", [], []) ["#),
+        "{native}"
+    );
+    assert!(native.contains(r#"Strong [Str "markdown"]"#), "{native}");
+}
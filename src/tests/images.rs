@@ -79,3 +79,272 @@ fn unresolvable_remote_images() {
     │ prefix test image suffix
     ");
 }
+
+#[test]
+fn empty_image_destinations() {
+    let book = MDBook::init()
+        .config(Config::markdown())
+        .chapter(Chapter::new(
+            "Some Chapter",
+            "prefix ![test image]() and <img src=\"\" alt=\"html image\"> suffix",
+            "chapter.md",
+        ))
+        .build();
+    insta::assert_snapshot!(book, @r"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  WARN mdbook_pandoc::preprocess: Image in chapter 'Some Chapter' has an empty destination; rendering its alt text instead    
+    │  WARN mdbook_pandoc::preprocess: Image in chapter 'Some Chapter' has an empty destination; rendering its alt text instead    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/markdown/book.md    
+    ├─ markdown/book.md
+    │ prefix test image and html image suffix
+    ");
+}
+
+#[test]
+fn figure_with_multiple_images() {
+    let book = MDBook::init()
+        .config(Config::latex())
+        .file_in_src("a.png", "")
+        .file_in_src("b.png", "")
+        .file_in_src("c.png", "")
+        .chapter(Chapter::new(
+            "",
+            r#"<figure><figure><img src="a.png"><figcaption>A</figcaption></figure><figure><img src="b.png"><figcaption>B</figcaption></figure><img src="c.png"><figcaption>Both</figcaption></figure>"#,
+            "chapter.md",
+        ))
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ \phantomsection\label{book__latex__src__chapter.md__mdbook-pandoc-chapter-start}
+    │ 
+    │ \pandocbounded{\includegraphics[keepaspectratio]{book/latex/src/a.png}}
+    │ 
+    │ A
+    │ 
+    │ \pandocbounded{\includegraphics[keepaspectratio]{book/latex/src/b.png}}
+    │ 
+    │ B
+    │ 
+    │ Both
+    ├─ latex/src/a.png
+    ├─ latex/src/b.png
+    ├─ latex/src/chapter.md
+    │ [Div ("mdbook-pandoc-chapter-start", [], []) [], RawBlock (Format "html") "<figure>", Div ("", [], []) [RawBlock (Format "html") "<figure>", Div ("", [], []) [Plain [Image ("", [], []) [] ("book/latex/src/a.png", ""), RawInline (Format "html") "<figcaption>"], Div ("", [], []) [Plain [Str "A"]], RawBlock (Format "html") "</figcaption>"], RawBlock (Format "html") "</figure>", RawBlock (Format "html") "<figure>", Div ("", [], []) [Plain [Image ("", [], []) [] ("book/latex/src/b.png", ""), RawInline (Format "html") "<figcaption>"], Div ("", [], []) [Plain [Str "B"]], RawBlock (Format "html") "</figcaption>"], RawBlock (Format "html") "</figure>", RawBlock (Format "html") "<figcaption>", Div ("", [], []) [Plain [Str "Both"]], RawBlock (Format "html") "</figcaption>"], RawBlock (Format "html") "</figure>"]
+    "#);
+}
+
+#[test]
+fn subfigures() {
+    let book = MDBook::init()
+        .config(Config {
+            subfigures: true,
+            ..Config::latex()
+        })
+        .file_in_src("a.png", "")
+        .file_in_src("b.png", "")
+        .file_in_src("c.png", "")
+        .chapter(Chapter::new(
+            "",
+            r#"<figure><figure><img src="a.png"><figcaption>A</figcaption></figure><figure><img src="b.png"><figcaption>B</figcaption></figure><img src="c.png"><figcaption>Both</figcaption></figure>"#,
+            "chapter.md",
+        ))
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ \phantomsection\label{book__latex__src__chapter.md__mdbook-pandoc-chapter-start}
+    │ 
+    │ \begin{figure}
+    │ \centering
+    │ 
+    │ \subcaptionbox{A}{\pandocbounded{\includegraphics[keepaspectratio]{book/latex/src/a.png}}}\hfill\subcaptionbox{B}{\pandocbounded{\includegraphics[keepaspectratio]{book/latex/src/b.png}}}
+    │ 
+    │ \pandocbounded{\includegraphics[keepaspectratio]{book/latex/src/c.png}}
+    │ 
+    │ \caption{Both}
+    │ 
+    │ \end{figure}
+    ├─ latex/src/a.png
+    ├─ latex/src/b.png
+    ├─ latex/src/c.png
+    ├─ latex/src/chapter.md
+    │ [Div ("mdbook-pandoc-chapter-start", [], []) [], RawBlock (Format "latex") "\\begin{figure}
+    │ \\centering", Para [RawInline (Format "latex") "\\subcaptionbox{", Str "A", RawInline (Format "latex") "}{", Image ("", [], []) [] ("book/latex/src/a.png", ""), RawInline (Format "latex") "}", RawInline (Format "latex") "\\hfill", RawInline (Format "latex") "\\subcaptionbox{", Str "B", RawInline (Format "latex") "}{", Image ("", [], []) [] ("book/latex/src/b.png", ""), RawInline (Format "latex") "}"], Plain [Image ("", [], []) [] ("book/latex/src/c.png", "")], Para [RawInline (Format "latex") "\\caption{", Str "Both", RawInline (Format "latex") "}"], RawBlock (Format "latex") "\\end{figure}"]
+    "#);
+}
+
+#[test]
+fn double_encoded_image_path() {
+    let book = MDBook::init()
+        .config(Config::pandoc())
+        .file_in_src("img/my image.png", "")
+        .chapter(Chapter::new("", "![](img/my%2520image.png)", "chapter.md"))
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/markdown/pandoc-ir    
+    ├─ markdown/pandoc-ir
+    │ [ Para
+    │     [ Image
+    │         ( "" , [] , [] )
+    │         []
+    │         ( "book/markdown/src/img/my image.png" , "" )
+    │     ]
+    │ ]
+    "#);
+}
+
+#[test]
+fn image_relative_to_source_dir() {
+    let book = MDBook::init()
+        .config(Config::latex())
+        .file_in_src("img/image.png", "")
+        .chapter(Chapter::new("", "![](img/image.png)", "chapter/chapter.md"))
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ \pandocbounded{\includegraphics[keepaspectratio]{book/latex/src/img/image.png}}
+    ├─ latex/src/chapter/chapter.md
+    │ [Para [Image ("", [], []) [] ("book/latex/src/img/image.png", "")]]
+    ├─ latex/src/img/image.png
+    "#);
+}
+
+#[test]
+fn inline_svg_as_vector_graphics() {
+    let cfg = indoc! {r#"
+        [output.pandoc.html]
+        svg-in-latex = "vector"
+
+        [output.pandoc.profile.latex]
+        output-file = "output.tex"
+        standalone = false
+    "#};
+    let book = MDBook::init()
+        .mdbook_config(cfg.parse().unwrap())
+        .chapter(Chapter::new(
+            "",
+            indoc! {r##"
+                <svg width="10" height="10">
+                <a xlink:href="#c"><text>a&nbsp;b</text></a><circle cx="5" cy="5" r="4"/>
+                </svg>
+            "##},
+            "chapter.md",
+        ))
+        .build();
+    let latex = std::fs::read_to_string(book.dir.join("latex/output.tex")).unwrap();
+    let path = latex
+        .split_once(r"\includesvg{")
+        .and_then(|(_, rest)| rest.split_once('}'))
+        .map(|(path, _)| path)
+        .unwrap_or_else(|| panic!("expected \\includesvg in output: {latex}"));
+    let svg = std::fs::read_to_string(book.dir.join(path.strip_prefix("book/").unwrap())).unwrap();
+    assert_eq!(
+        svg,
+        concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"10\" height=\"10\">\n",
+            "<a xmlns:xlink=\"http://www.w3.org/1999/xlink\" xlink:href=\"#c\"><text>a\u{a0}b</text></a>",
+            "<circle cx=\"5\" cy=\"5\" r=\"4\"/>\n</svg>",
+        )
+    );
+}
+
+#[test]
+fn max_image_width() {
+    let cfg = indoc! {r#"
+        [output.pandoc.profile.latex]
+        output-file = "output.tex"
+        standalone = false
+        max-image-width = "50%"
+    "#};
+    let book = MDBook::init()
+        .mdbook_config(cfg.parse().unwrap())
+        .file_in_src("img/image.png", "")
+        .chapter(Chapter::new(
+            "",
+            indoc! {r#"
+                ![alt text](img/image.png)
+                <img src="img/image.png" width="100">
+                <img src="img/image.png">
+            "#},
+            "chapter.md",
+        ))
+        .build();
+    let chapter = std::fs::read_to_string(book.dir.join("latex/src/chapter.md")).unwrap();
+    insta::assert_snapshot!(chapter, @r#"[Para [Image ("", [], [("width", "50%")]) [Str "alt text"] ("book/latex/src/img/image.png", ""), SoftBreak, Image ("", [], [("width", "100")]) [] ("book/latex/src/img/image.png", ""), SoftBreak, Image ("", [], [("width", "50%")]) [] ("book/latex/src/img/image.png", "")]]"#);
+}
+
+#[test]
+fn alt_text_as_caption() {
+    let build = |alt_as_caption| {
+        MDBook::init()
+            .config(Config {
+                alt_as_caption,
+                ..Config::latex()
+            })
+            .file_in_src("img/image.png", "")
+            .chapter(Chapter::new(
+                "",
+                indoc! {r#"
+                    ![A *small* diagram](img/image.png)
+
+                    Inline ![icon](img/image.png) image.
+                "#},
+                "chapter.md",
+            ))
+            .build()
+    };
+    let read =
+        |book: &super::BuildOutput, path| std::fs::read_to_string(book.dir.join(path)).unwrap();
+
+    let book = build(true);
+    let native = read(&book, "latex/src/chapter.md");
+    assert!(
+        native.starts_with(
+            r#"[Figure ("", [], []) (Caption Nothing [Plain [Str "A ", Emph [Str "small"], Str " diagram"]]) [Plain [Image"#
+        ),
+        "{native}"
+    );
+    let latex = read(&book, "latex/output.tex");
+    assert_eq!(latex.matches(r"\begin{figure}").count(), 1, "{latex}");
+    assert!(
+        latex.contains(r"\caption{A \emph{small} diagram}"),
+        "{latex}"
+    );
+
+    let book = build(false);
+    let latex = read(&book, "latex/output.tex");
+    assert!(!latex.contains(r"\begin{figure}"), "{latex}");
+}
+
+#[test]
+fn linked_image() {
+    let book = MDBook::init()
+        .config(Config::latex())
+        .file_in_src("dir/img/image.png", "")
+        .chapter(Chapter::new(
+            "",
+            "# One\n\n[![alt text](img/image.png)](two.md)",
+            "dir/one.md",
+        ))
+        .chapter(Chapter::new("", "# Two", "dir/two.md"))
+        .build();
+    let chapter = std::fs::read_to_string(book.dir.join("latex/src/dir/one.md")).unwrap();
+    insta::assert_snapshot!(chapter, @r#"[Header 1 ("one", [], []) [Str "One"], Para [Link ("", [], []) [Image ("", [], []) [Str "alt text"] ("book/latex/src/dir/img/image.png", "")] ("book/latex/src/dir/two.md#two", "")]]"#);
+}
@@ -1,4 +1,5 @@
 use indoc::indoc;
+use regex::Regex;
 
 use super::{Chapter, Config, MDBook};
 
@@ -53,6 +54,177 @@ fn link_title_containing_quotes() {
     "#);
 }
 
+#[test]
+fn dump_links() {
+    let book = MDBook::init()
+        .config(Config {
+            dump_links: true,
+            ..Config::latex()
+        })
+        .chapter(Chapter::new(
+            "",
+            "# One\n[Two](two.md) and [example](https://example.com)",
+            "one.md",
+        ))
+        .chapter(Chapter::new("", "# Two", "two.md"))
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/links.json
+    │ {
+    │   "one.md": [
+    │     {
+    │       "original": "two.md",
+    │       "resolved": "book/latex/src/two.md#two"
+    │     },
+    │     {
+    │       "original": "https://example.com",
+    │       "resolved": "https://example.com"
+    │     }
+    │   ]
+    │ }
+    ├─ latex/output.tex
+    │ \chapter{One}\label{book__latex__src__one.md__one}
+    │ 
+    │ \hyperref[book__latex__src__two.md__two]{Two} and
+    │ \href{https://example.com}{example}
+    │ 
+    │ \chapter{Two}\label{book__latex__src__two.md__two}
+    ├─ latex/src/one.md
+    │ [Header 1 ("one", [], []) [Str "One"], Para [Link ("", [], []) [Str "Two"] ("book/latex/src/two.md#two", ""), Str " and ", Link ("", [], []) [Str "example"] ("https://example.com", "")]]
+    ├─ latex/src/two.md
+    │ [Header 1 ("two", [], []) [Str "Two"]]
+    "#);
+}
+
+#[test]
+fn link_to_chapter_with_markdown_extension() {
+    let book = MDBook::init()
+        .config(Config::latex())
+        .chapter(Chapter::new(
+            "",
+            "# One\n[Foo](foo.md) [Foo](foo.html) [Foo](foo.markdown)",
+            "one.md",
+        ))
+        .chapter(Chapter::new("", "# Foo", "foo.markdown"))
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ \chapter{One}\label{book__latex__src__one.md__one}
+    │ 
+    │ \hyperref[book__latex__src__foo.markdown__foo]{Foo}
+    │ \hyperref[book__latex__src__foo.markdown__foo]{Foo}
+    │ \hyperref[book__latex__src__foo.markdown__foo]{Foo}
+    │ 
+    │ \chapter{Foo}\label{book__latex__src__foo.markdown__foo}
+    ├─ latex/src/foo.markdown
+    │ [Header 1 ("foo", [], []) [Str "Foo"]]
+    ├─ latex/src/one.md
+    │ [Header 1 ("one", [], []) [Str "One"], Para [Link ("", [], []) [Str "Foo"] ("book/latex/src/foo.markdown#foo", ""), Str " ", Link ("", [], []) [Str "Foo"] ("book/latex/src/foo.markdown#foo", ""), Str " ", Link ("", [], []) [Str "Foo"] ("book/latex/src/foo.markdown#foo", "")]]
+    "#);
+}
+
+#[test]
+fn id_prefix() {
+    let book = MDBook::init()
+        .config(Config {
+            id_prefix: Some("{chapter}-".into()),
+            ..Config::latex()
+        })
+        .chapter(Chapter::new(
+            "",
+            "# One\n[Two](nested/two.md) [Sub](nested/two.md#sub) [Local](#one)",
+            "one.md",
+        ))
+        .chapter(Chapter::new("", "# Two\n## Sub", "nested/two.md"))
+        .build();
+    insta::assert_snapshot!(book, @r##"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ \chapter{One}\label{one-one}
+    │ 
+    │ \hyperref[nested-two-two]{Two} \hyperref[nested-two-sub]{Sub}
+    │ \hyperref[one-one]{Local}
+    │ 
+    │ \chapter{Two}\label{nested-two-two}
+    │ 
+    │ \section*{Sub}\label{nested-two-sub}
+    ├─ latex/src/nested/two.md
+    │ [Header 1 ("nested-two-two", [], []) [Str "Two"], Header 2 ("nested-two-sub", ["unnumbered", "unlisted"], []) [Str "Sub"]]
+    ├─ latex/src/one.md
+    │ [Header 1 ("one-one", [], []) [Str "One"], Para [Link ("", [], []) [Str "Two"] ("#nested-two-two", ""), Str " ", Link ("", [], []) [Str "Sub"] ("#nested-two-sub", ""), Str " ", Link ("", [], []) [Str "Local"] ("#one-one", "")]]
+    "##);
+}
+
+#[test]
+fn id_prefix_with_similar_chapter_paths() {
+    let book = MDBook::init()
+        .config(Config {
+            id_prefix: Some("{chapter}-".into()),
+            ..Config::latex()
+        })
+        .chapter(Chapter::new("", "# B\n[Other](../a_b.md#b)", "a/b.md"))
+        .chapter(Chapter::new("", "# B\n[Other](a/b.md#b)", "a_b.md"))
+        .build();
+    insta::assert_snapshot!(book, @r##"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ \chapter{B}\label{a-b-b}
+    │ 
+    │ \hyperref[a-b-2-b]{Other}
+    │ 
+    │ \chapter{B}\label{a-b-2-b}
+    │ 
+    │ \hyperref[a-b-b]{Other}
+    ├─ latex/src/a/b.md
+    │ [Header 1 ("a-b-b", [], []) [Str "B"], Para [Link ("", [], []) [Str "Other"] ("#a-b-2-b", "")]]
+    ├─ latex/src/a_b.md
+    │ [Header 1 ("a-b-2-b", [], []) [Str "B"], Para [Link ("", [], []) [Str "Other"] ("#a-b-b", "")]]
+    "##);
+}
+
+#[test]
+fn fixed_id_prefix_with_duplicate_headings() {
+    let book = MDBook::init()
+        .config(Config {
+            id_prefix: Some("doc-".into()),
+            ..Config::latex()
+        })
+        .chapter(Chapter::new("One", "# Intro\n## Intro", "one.md"))
+        .chapter(Chapter::new("Two", "# Intro", "two.md"))
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  WARN mdbook_pandoc::preprocess: Heading id 'intro' in chapter 'Two' is already used in chapter 'One'    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ \chapter{Intro}\label{doc-intro}
+    │ 
+    │ \section*{Intro}\label{doc-intro-1}
+    │ 
+    │ \chapter{Intro}\label{doc-intro}
+    ├─ latex/src/one.md
+    │ [Header 1 ("doc-intro", [], []) [Str "Intro"], Header 2 ("doc-intro-1", ["unnumbered", "unlisted"], []) [Str "Intro"]]
+    ├─ latex/src/two.md
+    │ [Header 1 ("doc-intro", [], []) [Str "Intro"]]
+    "#);
+}
+
 #[test]
 fn single_chapter_with_explicit_self_link() {
     let book = MDBook::init()
@@ -98,6 +270,7 @@ fn inter_chapter_links() {
     │  INFO mdbook::book: Running the pandoc backend    
     │  WARN mdbook_pandoc::preprocess: Failed to determine suitable anchor for beginning of chapter 'Three'--does it contain any headings?    
     │  WARN mdbook_pandoc::preprocess: Unable to normalize link '../three.md' in chapter 'Two': failed to link to beginning of chapter    
+    │  WARN mdbook_pandoc::preprocess: Chapter 'Three' is empty    
     │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
     │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
     ├─ latex/output.tex
@@ -118,3 +291,133 @@ fn inter_chapter_links() {
     │ [Header 1 ("two", [], []) [Str "Two"], Para [Link ("", [], []) [Str "One"] ("book/latex/src/one/one.md#one", ""), SoftBreak, Link ("", [], []) [Str "also one"] ("book/latex/src/one/one.md#one", ""), SoftBreak, Link ("", [], []) [Str "Three"] ("../three.md", "")]]
     "#);
 }
+
+#[test]
+fn preserve_md_links_in_markdown_output() {
+    let cfg = indoc! {r#"
+        [output.pandoc]
+        keep-preprocessed = false
+
+        [output.pandoc.profile.commonmark]
+        output-file = "book.md"
+        to = "commonmark"
+        standalone = false
+        preserve-md-links = true
+    "#};
+    let book = MDBook::init()
+        .mdbook_config(cfg.parse().unwrap())
+        .chapter(Chapter::new(
+            "One",
+            "# One\n[Two](../two/two.md#two)",
+            "one/one.md",
+        ))
+        .chapter(Chapter::new(
+            "Two",
+            "# Two\n[One](../one/one.md)",
+            "two/two.md",
+        ))
+        .build();
+    insta::assert_snapshot!(book, @r"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/commonmark/book.md    
+    ├─ commonmark/book.md
+    │ # One
+    │ 
+    │ [Two](../two/two.md#two)
+    │ 
+    │ # Two
+    │ 
+    │ [One](../one/one.md)
+    ");
+}
+
+#[test]
+fn link_extension_in_html_output() {
+    let cfg = indoc! {r#"
+        [output.pandoc.html]
+        link-extension = "EXTENSION"
+
+        [output.pandoc.profile.html]
+        output-file = "book.html"
+        standalone = false
+    "#};
+    let build = |extension: &str| {
+        let book = MDBook::init()
+            .mdbook_config(cfg.replace("EXTENSION", extension).parse().unwrap())
+            .chapter(Chapter::new(
+                "One",
+                "# One\n[Two](../two/two.md#two)",
+                "one/one.md",
+            ))
+            .chapter(Chapter::new(
+                "Two",
+                "# Two\n[One](../one/one.md)",
+                "two/two.md",
+            ))
+            .build();
+        std::fs::read_to_string(book.dir.join("html/book.html")).unwrap()
+    };
+
+    let html = build("");
+    assert!(html.contains(r#"href="../two/two#two""#), "{html}");
+    assert!(html.contains(r#"href="../one/one""#), "{html}");
+
+    let html = build(".html");
+    assert!(html.contains(r#"href="../two/two.html#two""#), "{html}");
+    assert!(html.contains(r#"href="../one/one.html""#), "{html}");
+}
+
+#[test]
+fn manifest_of_files_outside_source_dir() {
+    let book = MDBook::init()
+        .config(Config::latex())
+        .file_in_root("outside.txt", "some text")
+        .file_in_src("manifest.txt", "the book's own manifest")
+        .file_in_src("src-manifest.txt", "the book's own manifest")
+        .chapter(Chapter::new("", "[outside](../outside.txt)", "chapter.md"))
+        .build()
+        .to_string();
+    let manifest =
+        Regex::new(r"├─ latex/src-manifest.txt\n│ ([0-9a-f]+\.txt) => outside.txt\n").unwrap();
+    let hashed = &manifest
+        .captures(&book)
+        .unwrap_or_else(|| panic!("manifest should map hashed file to its source:\n{book}"))[1];
+    assert!(book.contains(&format!("├─ latex/src/{hashed}\n│ some text\n")));
+    // Files from the source directory are left alone
+    assert!(book.contains("├─ latex/src/manifest.txt\n│ the book's own manifest\n"));
+    assert!(book.contains("├─ latex/src/src-manifest.txt\n│ the book's own manifest\n"));
+}
+
+#[test]
+fn link_to_html_only_chapter() {
+    let book = MDBook::init()
+        .chapter(Chapter::new("One", "# One\n[Widget](widget.md)", "one.md"))
+        .chapter(Chapter::new(
+            "Widget",
+            r#"<div id="widget">some text here</div>"#,
+            "widget.md",
+        ))
+        .config(Config::latex())
+        .build();
+    insta::assert_snapshot!(book, @r#"
+    ├─ log output
+    │  INFO mdbook::book: Running the pandoc backend    
+    │  INFO mdbook_pandoc::pandoc::renderer: Running pandoc    
+    │  INFO mdbook_pandoc::pandoc::renderer: Wrote output to book/latex/output.tex    
+    ├─ latex/output.tex
+    │ \chapter{One}\label{book__latex__src__one.md__one}
+    │ 
+    │ \hyperref[book__latex__src__widget.md__mdbook-pandoc-chapter-start]{Widget}
+    │ 
+    │ \phantomsection\label{book__latex__src__widget.md__mdbook-pandoc-chapter-start}
+    │ 
+    │ \phantomsection\label{book__latex__src__widget.md__widget}
+    │ some text here
+    ├─ latex/src/one.md
+    │ [Header 1 ("one", [], []) [Str "One"], Para [Link ("", [], []) [Str "Widget"] ("book/latex/src/widget.md#mdbook-pandoc-chapter-start", "")]]
+    ├─ latex/src/widget.md
+    │ [Div ("mdbook-pandoc-chapter-start", [], []) [], Div ("widget", [], []) [Plain [Str "some text here"]]]
+    "#);
+}
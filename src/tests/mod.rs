@@ -176,6 +176,15 @@ impl MDBook {
         self
     }
 
+    /// Adds a chapter mdBook doesn't number, like a prefix chapter in `SUMMARY.md`.
+    pub fn unnumbered_chapter(mut self, chapter: Chapter) -> Self {
+        self = self.chapter(chapter);
+        if let Some(BookItem::Chapter(chapter)) = self.book.book.sections.last_mut() {
+            chapter.number = None;
+        }
+        self
+    }
+
     pub fn part(mut self, name: impl Into<String>) -> Self {
         self.book.book.push_item(BookItem::PartTitle(name.into()));
         self
@@ -199,6 +208,16 @@ impl MDBook {
         self
     }
 
+    #[cfg(unix)]
+    pub fn symlink_in_src(self, path: impl AsRef<Path>, target: impl AsRef<Path>) -> Self {
+        let path = self.book.source_dir().join(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        std::os::unix::fs::symlink(target, path).unwrap();
+        self
+    }
+
     pub fn build(mut self) -> BuildOutput {
         let mut renderer = Renderer::new();
         renderer.logfile = Some(self.logfile.try_clone().unwrap());
@@ -371,6 +390,18 @@ impl Config {
         .unwrap()
     }
 
+    fn html() -> Self {
+        toml! {
+            keep-preprocessed = false
+
+            [profile.html]
+            output-file = "book.html"
+            standalone = false
+        }
+        .try_into()
+        .unwrap()
+    }
+
     fn pandoc() -> Self {
         toml! {
             keep-preprocessed = false
@@ -386,6 +417,7 @@ impl Config {
 }
 
 mod basic;
+mod cache;
 mod config;
 mod escaping;
 
@@ -1,4 +1,4 @@
-use std::str::FromStr;
+use std::{fs, str::FromStr};
 
 use indoc::indoc;
 
@@ -52,3 +52,68 @@ fn redirects() {
     │ [Header 1 ("new-new-bar", [], []) [Str "New New Bar"]]
     "#)
 }
+
+#[test]
+fn chained_redirects_resolve_deterministically() {
+    let cfg = indoc! {r#"
+        [output.pandoc.profile.test]
+        output-file = "/dev/null"
+        to = "markdown"
+
+        [output.html.redirect]
+        "/a.html" = "b.html"
+        "/b.html" = "c.html"
+        "/c.html" = "d.html"
+        "/d.html" = "target.html"
+        "/e.html" = "a.html"
+    "#};
+    let build = || {
+        let book = MDBook::init()
+            .mdbook_config(mdbook::Config::from_str(cfg).unwrap())
+            .chapter(Chapter::new(
+                "",
+                "[a](a.html)\n[c](c.html)\n[e](e.html)",
+                "index.md",
+            ))
+            .chapter(Chapter::new("", "# Target", "target.md"))
+            .build();
+        fs::read_to_string(book.dir.join("test/src/index.md")).unwrap()
+    };
+    let index = build();
+    assert_eq!(
+        index
+            .matches(r#"("book/test/src/target.md#target", "")"#)
+            .count(),
+        3,
+        "{index}"
+    );
+    for _ in 0..3 {
+        assert_eq!(build(), index);
+    }
+}
+
+#[test]
+fn redirect_cycle() {
+    let cfg = indoc! {r#"
+        [output.pandoc.profile.test]
+        output-file = "/dev/null"
+        to = "markdown"
+
+        [output.html.redirect]
+        "/a.html" = "b.html"
+        "/b.html" = "c.html"
+        "/c.html" = "a.html"
+    "#};
+    let book = MDBook::init()
+        .mdbook_config(mdbook::Config::from_str(cfg).unwrap())
+        .chapter(Chapter::new("", "[a](a.html)", "index.md"))
+        .build();
+    assert!(
+        book.logs.contains(
+            "Failed to resolve redirect: /c.html => a.html: \
+            Cycle in redirects: 'book/test/src/c.html' redirects back to itself"
+        ),
+        "{}",
+        book.logs
+    );
+}
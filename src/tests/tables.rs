@@ -1,3 +1,5 @@
+use std::fs;
+
 use indoc::indoc;
 
 use super::{Chapter, Config, MDBook};
@@ -76,3 +78,114 @@ fn wide_table() {
     │ [Table ("", [], []) (Caption Nothing []) [(AlignDefault, (ColWidth 0.09859154929577464)), (AlignLeft, (ColWidth 0.9014084507042254))] (TableHead ("", [], []) [Row ("", [], []) [Cell ("", [], []) AlignDefault (RowSpan 0) (ColSpan 0) [Plain [Str "Header1"]], Cell ("", [], []) AlignDefault (RowSpan 0) (ColSpan 0) [Plain [Str "Header2"]]]]) [(TableBody ("", [], []) (RowHeadColumns 0) [] [Row ("", [], []) [Cell ("", [], []) AlignDefault (RowSpan 0) (ColSpan 0) [Plain [Str "abc"]], Cell ("", [], []) AlignDefault (RowSpan 0) (ColSpan 0) [Plain [Str "long long long long long long long long long long long long long"]]]])] (TableFoot ("", [], []) [])]
     "#);
 }
+
+#[test]
+fn html_table_matches_markdown_table() {
+    let markdown = indoc! {"
+        | Header1 | Header2 |
+        |:--------|--------:|
+        | abc     | def     |
+    "};
+    let html = indoc! {r#"
+        <table>
+          <thead>
+            <tr><th align="left">Header1</th><th align="right">Header2</th></tr>
+          </thead>
+          <tbody>
+            <!-- comment -->
+            <tr><td>abc</td><td>def</td></tr>
+          </tbody>
+        </table>
+    "#};
+    let book = MDBook::init()
+        .chapter(Chapter::new("", markdown, "markdown.md"))
+        .chapter(Chapter::new("", html, "html.md"))
+        .config(Config::latex())
+        .build();
+    let markdown = fs::read_to_string(book.dir.join("latex/src/markdown.md")).unwrap();
+    let html = fs::read_to_string(book.dir.join("latex/src/html.md")).unwrap();
+    assert_eq!(markdown, html);
+    insta::assert_snapshot!(html, @r#"[Table ("", [], []) (Caption Nothing []) [(AlignLeft, ColWidthDefault), (AlignRight, ColWidthDefault)] (TableHead ("", [], []) [Row ("", [], []) [Cell ("", [], []) AlignDefault (RowSpan 0) (ColSpan 0) [Plain [Str "Header1"]], Cell ("", [], []) AlignDefault (RowSpan 0) (ColSpan 0) [Plain [Str "Header2"]]]]) [(TableBody ("", [], []) (RowHeadColumns 0) [] [Row ("", [], []) [Cell ("", [], []) AlignDefault (RowSpan 0) (ColSpan 0) [Plain [Str "abc"]], Cell ("", [], []) AlignDefault (RowSpan 0) (ColSpan 0) [Plain [Str "def"]]]])] (TableFoot ("", [], []) [])]"#);
+}
+
+#[test]
+fn html_table_cell_alignment() {
+    let html = indoc! {r#"
+        <table>
+          <tbody>
+            <tr><td>Item</td><td>Price</td></tr>
+            <tr><td>Apple</td><td align="right">1.00</td></tr>
+          </tbody>
+        </table>
+    "#};
+    let book = MDBook::init()
+        .chapter(Chapter::new("", html, "chapter.md"))
+        .config(Config::latex())
+        .build();
+    let chapter = fs::read_to_string(book.dir.join("latex/src/chapter.md")).unwrap();
+    insta::assert_snapshot!(chapter, @r#"[Table ("", [], []) (Caption Nothing []) [(AlignDefault, ColWidthDefault), (AlignDefault, ColWidthDefault)] (TableHead ("", [], []) []) [(TableBody ("", [], []) (RowHeadColumns 0) [] [Row ("", [], []) [Cell ("", [], []) AlignDefault (RowSpan 0) (ColSpan 0) [Plain [Str "Item"]], Cell ("", [], []) AlignDefault (RowSpan 0) (ColSpan 0) [Plain [Str "Price"]]], Row ("", [], []) [Cell ("", [], []) AlignDefault (RowSpan 0) (ColSpan 0) [Plain [Str "Apple"]], Cell ("", [], []) AlignRight (RowSpan 0) (ColSpan 0) [Plain [Str "1.00"]]]])] (TableFoot ("", [], []) [])]"#);
+}
+
+#[test]
+fn table_columns() {
+    let table = indoc! {"
+        | Header1 | Header2 |
+        | ------- | :--------------------------------------------------------------- |
+        | abc     | long long long long long long long long long long long long long |
+    "};
+    let book = MDBook::init()
+        .chapter(Chapter::new("", table, "chapter.md"))
+        .config(Config {
+            table_columns: Some(100),
+            ..Config::latex()
+        })
+        .build();
+    let chapter = fs::read_to_string(book.dir.join("latex/src/chapter.md")).unwrap();
+    insta::assert_snapshot!(chapter, @r#"[Table ("", [], []) (Caption Nothing []) [(AlignDefault, ColWidthDefault), (AlignLeft, ColWidthDefault)] (TableHead ("", [], []) [Row ("", [], []) [Cell ("", [], []) AlignDefault (RowSpan 0) (ColSpan 0) [Plain [Str "Header1"]], Cell ("", [], []) AlignDefault (RowSpan 0) (ColSpan 0) [Plain [Str "Header2"]]]]) [(TableBody ("", [], []) (RowHeadColumns 0) [] [Row ("", [], []) [Cell ("", [], []) AlignDefault (RowSpan 0) (ColSpan 0) [Plain [Str "abc"]], Cell ("", [], []) AlignDefault (RowSpan 0) (ColSpan 0) [Plain [Str "long long long long long long long long long long long long long"]]]])] (TableFoot ("", [], []) [])]"#);
+
+    let book = MDBook::init()
+        .chapter(Chapter::new(
+            "",
+            "| A | B |\n|---|---|\n| a | b |",
+            "chapter.md",
+        ))
+        .config(Config {
+            table_columns: Some(5),
+            ..Config::latex()
+        })
+        .build();
+    let chapter = fs::read_to_string(book.dir.join("latex/src/chapter.md")).unwrap();
+    assert!(chapter.contains("(ColWidth 0.5)"), "{chapter}");
+}
+
+#[test]
+fn mismatched_delimiter_row() {
+    let build = |table| {
+        MDBook::init()
+            .chapter(Chapter::new("", table, "chapter.md"))
+            .config(Config {
+                table_columns: Some(5),
+                ..Config::latex()
+            })
+            .build()
+    };
+
+    // A delimiter row with an extra column doesn't form a table
+    let book = build("| A | B |\n|---|---|---|\n| a | b |");
+    assert!(!book.logs.contains("ERROR"), "{}", book.logs);
+    let chapter = fs::read_to_string(book.dir.join("latex/src/chapter.md")).unwrap();
+    assert!(!chapter.contains("Table"), "{chapter}");
+    assert!(chapter.contains(r#"Str "a""#), "{chapter}");
+
+    // Block quote markers aren't counted as columns
+    let book = build("> | A | B |\n> |---|------|\n> | a | b |");
+    assert!(!book.logs.contains("WARN"), "{}", book.logs);
+    let chapter = fs::read_to_string(book.dir.join("latex/src/chapter.md")).unwrap();
+    assert!(
+        chapter.contains(
+            "[(AlignDefault, (ColWidth 0.3333333333333333)), \
+            (AlignDefault, (ColWidth 0.6666666666666666))]"
+        ),
+        "{chapter}"
+    );
+}
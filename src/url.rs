@@ -0,0 +1,41 @@
+/// Percent-decodes `s`, repeating to undo multiple rounds of encoding (e.g. `%2520` -> `%20` -> ` `).
+///
+/// Returns `None` if `s` contains no percent-encoded characters or does not decode to valid UTF8.
+pub fn decode(s: &str) -> Option<String> {
+    fn decode_once(s: &str) -> Option<String> {
+        let bytes = s.as_bytes();
+        let mut decoded = Vec::with_capacity(bytes.len());
+        let mut idx = 0;
+        while idx < bytes.len() {
+            let byte = (bytes[idx] == b'%')
+                .then(|| bytes.get(idx + 1..idx + 3))
+                .flatten()
+                .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+            match byte {
+                Some(byte) => {
+                    decoded.push(byte);
+                    idx += 3;
+                }
+                None => {
+                    decoded.push(bytes[idx]);
+                    idx += 1;
+                }
+            }
+        }
+        String::from_utf8(decoded)
+            .ok()
+            .filter(|decoded| decoded.as_str() != s)
+    }
+
+    // Only undo a few rounds of encoding to avoid mangling strings that legitimately contain `%`
+    const MAX_ROUNDS: usize = 3;
+    let mut decoded = decode_once(s)?;
+    for _ in 1..MAX_ROUNDS {
+        match decode_once(&decoded) {
+            Some(again) => decoded = again,
+            None => break,
+        }
+    }
+    Some(decoded)
+}